@@ -0,0 +1,9 @@
+CREATE TABLE webhook_deliveries (
+    id serial primary key,
+    event text not null,
+    url text not null,
+    payload text not null,
+    status_code int,
+    error text,
+    delivered_at timestamp with time zone not null default CURRENT_TIMESTAMP
+)
\ No newline at end of file
@@ -0,0 +1 @@
+DROP TABLE cross_posts
\ No newline at end of file
@@ -0,0 +1,10 @@
+CREATE TABLE cross_posts (
+    id serial primary key,
+    answer_id int references answers (id) on delete cascade not null,
+    service text not null,
+    remote_id text,
+    remote_url text,
+    error text,
+    updated_at timestamp with time zone not null default CURRENT_TIMESTAMP,
+    unique (answer_id, service)
+)
\ No newline at end of file
@@ -0,0 +1 @@
+DROP TABLE admin_credentials
\ No newline at end of file
@@ -0,0 +1,5 @@
+CREATE TABLE admin_credentials (
+    username text primary key,
+    password_hash text not null,
+    updated_at timestamp with time zone not null default CURRENT_TIMESTAMP
+)
\ No newline at end of file
@@ -0,0 +1,3 @@
+DROP TABLE admin_recovery_codes;
+ALTER TABLE admin_credentials DROP COLUMN totp_enabled;
+ALTER TABLE admin_credentials DROP COLUMN totp_secret;
\ No newline at end of file
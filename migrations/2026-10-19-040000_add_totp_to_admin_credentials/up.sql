@@ -0,0 +1,8 @@
+ALTER TABLE admin_credentials ADD COLUMN totp_secret text;
+ALTER TABLE admin_credentials ADD COLUMN totp_enabled boolean not null default 'f';
+CREATE TABLE admin_recovery_codes (
+    id serial primary key,
+    username text references admin_credentials (username) on delete cascade on update cascade not null,
+    code_hash text not null,
+    used_at timestamp with time zone
+);
\ No newline at end of file
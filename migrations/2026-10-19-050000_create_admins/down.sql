@@ -0,0 +1,3 @@
+ALTER TABLE admins DROP COLUMN created_at;
+ALTER TABLE admins DROP COLUMN role;
+ALTER TABLE admins RENAME TO admin_credentials;
@@ -0,0 +1,3 @@
+ALTER TABLE admin_credentials RENAME TO admins;
+ALTER TABLE admins ADD COLUMN role text not null default 'owner' check (role in ('owner', 'answerer', 'moderator'));
+ALTER TABLE admins ADD COLUMN created_at timestamp with time zone not null default CURRENT_TIMESTAMP;
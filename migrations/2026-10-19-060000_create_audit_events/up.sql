@@ -0,0 +1,22 @@
+CREATE TABLE audit_events (
+    id serial primary key,
+    actor text not null,
+    action text not null,
+    target_id text not null,
+    before_value text,
+    after_value text,
+    created_at timestamp with time zone not null default CURRENT_TIMESTAMP
+);
+CREATE INDEX audit_events_created_at_idx ON audit_events (created_at);
+CREATE INDEX audit_events_action_idx ON audit_events (action);
+
+-- audit events are append-only
+CREATE FUNCTION reject_audit_event_modification() RETURNS trigger AS $$
+BEGIN
+    RAISE EXCEPTION 'audit_events is append-only';
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER audit_events_append_only
+    BEFORE UPDATE OR DELETE OR TRUNCATE ON audit_events
+    FOR EACH STATEMENT EXECUTE PROCEDURE reject_audit_event_modification();
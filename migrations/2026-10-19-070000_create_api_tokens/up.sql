@@ -0,0 +1,10 @@
+CREATE TABLE api_tokens (
+    id serial primary key,
+    username text not null references admins (username) on delete cascade,
+    name text not null,
+    token_hash text not null unique,
+    scope text not null check (scope in ('read', 'write')),
+    created_at timestamp with time zone not null default CURRENT_TIMESTAMP,
+    last_used_at timestamp with time zone,
+    revoked_at timestamp with time zone
+)
@@ -0,0 +1,2 @@
+DROP TABLE blocked_senders;
+ALTER TABLE questions DROP COLUMN sender_hash;
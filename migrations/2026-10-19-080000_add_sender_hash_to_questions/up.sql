@@ -0,0 +1,8 @@
+ALTER TABLE questions ADD COLUMN sender_hash text;
+CREATE INDEX questions_sender_hash_idx ON questions (sender_hash);
+
+CREATE TABLE blocked_senders (
+    sender_hash text primary key,
+    blocked_by text not null,
+    created_at timestamp with time zone not null default CURRENT_TIMESTAMP
+);
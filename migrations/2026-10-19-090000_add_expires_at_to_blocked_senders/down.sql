@@ -0,0 +1 @@
+ALTER TABLE blocked_senders DROP COLUMN expires_at
@@ -0,0 +1 @@
+ALTER TABLE blocked_senders ADD COLUMN expires_at timestamp with time zone
@@ -0,0 +1,10 @@
+CREATE TABLE content_rules (
+    id serial primary key,
+    pattern text not null,
+    is_regex boolean not null default false,
+    action text not null check (action in ('reject', 'hide', 'flag')),
+    created_by text not null,
+    created_at timestamp with time zone not null default CURRENT_TIMESTAMP
+);
+
+ALTER TABLE questions ADD COLUMN flagged boolean not null default false;
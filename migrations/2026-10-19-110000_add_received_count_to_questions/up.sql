@@ -0,0 +1 @@
+ALTER TABLE questions ADD COLUMN received_count integer not null default 1
@@ -0,0 +1 @@
+DROP TABLE spam_counters
@@ -0,0 +1,6 @@
+CREATE TABLE spam_counters (
+    day date not null,
+    reason text not null,
+    count integer not null default 0,
+    primary key (day, reason)
+)
@@ -0,0 +1,4 @@
+ALTER TABLE questions DROP COLUMN trained_label;
+ALTER TABLE questions DROP COLUMN spam_score;
+DROP TABLE classifier_documents;
+DROP TABLE classifier_tokens;
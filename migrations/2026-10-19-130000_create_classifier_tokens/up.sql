@@ -0,0 +1,13 @@
+CREATE TABLE classifier_tokens (
+    token text primary key,
+    spam_count integer not null default 0,
+    ham_count integer not null default 0
+);
+
+CREATE TABLE classifier_documents (
+    label text primary key check (label in ('spam', 'ham')),
+    count integer not null default 0
+);
+
+ALTER TABLE questions ADD COLUMN spam_score double precision;
+ALTER TABLE questions ADD COLUMN trained_label text check (trained_label in ('spam', 'ham'));
@@ -0,0 +1,8 @@
+ALTER TABLE questions ADD COLUMN hidden boolean not null default false;
+ALTER TABLE questions ADD COLUMN flagged boolean not null default false;
+
+UPDATE questions SET hidden = true WHERE status in ('hidden', 'spam');
+UPDATE questions SET flagged = true WHERE status = 'pending';
+
+DROP INDEX questions_status_created_at_idx;
+ALTER TABLE questions DROP COLUMN status;
@@ -0,0 +1,11 @@
+ALTER TABLE questions ADD COLUMN status text not null default 'inbox'
+    check (status in ('pending', 'inbox', 'hidden', 'spam', 'answered'));
+
+UPDATE questions SET status = 'pending' WHERE flagged;
+UPDATE questions SET status = 'hidden' WHERE hidden;
+UPDATE questions SET status = 'answered' WHERE id IN (SELECT question_id FROM answers);
+
+ALTER TABLE questions DROP COLUMN hidden;
+ALTER TABLE questions DROP COLUMN flagged;
+
+CREATE INDEX questions_status_created_at_idx ON questions (status, created_at);
@@ -0,0 +1,2 @@
+ALTER TABLE answers DROP COLUMN published;
+DROP TABLE answer_reports;
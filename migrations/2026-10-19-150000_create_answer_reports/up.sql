@@ -0,0 +1,10 @@
+CREATE TABLE answer_reports (
+    id serial primary key,
+    answer_id integer not null references answers (id) on delete cascade,
+    reason text not null,
+    reporter_hash text not null,
+    created_at timestamp with time zone not null default CURRENT_TIMESTAMP,
+    unique (answer_id, reporter_hash)
+);
+
+ALTER TABLE answers ADD COLUMN published boolean not null default true;
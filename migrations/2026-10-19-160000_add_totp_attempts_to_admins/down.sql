@@ -0,0 +1,3 @@
+ALTER TABLE admins DROP COLUMN totp_last_step;
+ALTER TABLE admins DROP COLUMN totp_locked_until;
+ALTER TABLE admins DROP COLUMN totp_failed_attempts;
@@ -0,0 +1,3 @@
+ALTER TABLE admins ADD COLUMN totp_failed_attempts integer not null default 0;
+ALTER TABLE admins ADD COLUMN totp_locked_until timestamp with time zone;
+ALTER TABLE admins ADD COLUMN totp_last_step bigint;
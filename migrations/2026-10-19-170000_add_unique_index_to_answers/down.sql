@@ -0,0 +1 @@
+DROP INDEX answers_question_id_idx
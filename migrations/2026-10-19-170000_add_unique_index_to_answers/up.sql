@@ -0,0 +1 @@
+CREATE UNIQUE INDEX answers_question_id_idx ON answers (question_id)
@@ -0,0 +1,16 @@
+use hex;
+use rand::Rng;
+use sha2::{Digest, Sha256};
+
+// makes leaked tokens easy to find with secret scanners
+const PREFIX: &'static str = "reing_";
+
+pub fn generate() -> String {
+    format!("{}{}", PREFIX, hex::encode(rand::thread_rng().gen::<[u8; 32]>()))
+}
+
+// Tokens are random, so a plain SHA-256 is enough to keep them unusable if
+// the database leaks, and it lets us look them up by hash.
+pub fn hash(token: &str) -> String {
+    hex::encode(Sha256::digest(token.trim().as_bytes()))
+}
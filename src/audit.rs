@@ -0,0 +1,143 @@
+use model;
+use serde::Serialize;
+use serde_json;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Action {
+    QuestionHidden,
+    QuestionUnhidden,
+    QuestionStatusUpdated,
+    QuestionsBulkModerated,
+    SenderBlocked,
+    SenderUnblocked,
+    ContentRuleCreated,
+    ContentRuleDeleted,
+    AnswerCreated,
+    AnswerUpdated,
+    AnswerDeleted,
+    AnswerCrossPosted,
+    AnswerPublished,
+    AnswerUnpublished,
+    AnswerReportsDismissed,
+    AdminCreated,
+    AdminRoleUpdated,
+    AdminPasswordUpdated,
+    AdminDeleted,
+    TotpEnabled,
+    TotpDisabled,
+    ApiTokenCreated,
+    ApiTokenRevoked,
+}
+
+impl Action {
+    pub fn all() -> Vec<Action> {
+        vec![
+            Action::QuestionHidden,
+            Action::QuestionUnhidden,
+            Action::QuestionStatusUpdated,
+            Action::QuestionsBulkModerated,
+            Action::SenderBlocked,
+            Action::SenderUnblocked,
+            Action::ContentRuleCreated,
+            Action::ContentRuleDeleted,
+            Action::AnswerCreated,
+            Action::AnswerUpdated,
+            Action::AnswerDeleted,
+            Action::AnswerCrossPosted,
+            Action::AnswerPublished,
+            Action::AnswerUnpublished,
+            Action::AnswerReportsDismissed,
+            Action::AdminCreated,
+            Action::AdminRoleUpdated,
+            Action::AdminPasswordUpdated,
+            Action::AdminDeleted,
+            Action::TotpEnabled,
+            Action::TotpDisabled,
+            Action::ApiTokenCreated,
+            Action::ApiTokenRevoked,
+        ]
+    }
+
+    // Replaced by QuestionStatusUpdated. Kept to filter earlier events.
+    pub fn is_legacy(&self) -> bool {
+        match self {
+            Action::QuestionHidden | Action::QuestionUnhidden => true,
+            _ => false,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Action::QuestionHidden => "question.hidden",
+            Action::QuestionUnhidden => "question.unhidden",
+            Action::QuestionStatusUpdated => "question.status_updated",
+            Action::QuestionsBulkModerated => "question.bulk_moderated",
+            Action::SenderBlocked => "sender.blocked",
+            Action::SenderUnblocked => "sender.unblocked",
+            Action::ContentRuleCreated => "content_rule.created",
+            Action::ContentRuleDeleted => "content_rule.deleted",
+            Action::AnswerCreated => "answer.created",
+            Action::AnswerUpdated => "answer.updated",
+            Action::AnswerDeleted => "answer.deleted",
+            Action::AnswerCrossPosted => "answer.cross_posted",
+            Action::AnswerPublished => "answer.published",
+            Action::AnswerUnpublished => "answer.unpublished",
+            Action::AnswerReportsDismissed => "answer.reports_dismissed",
+            Action::AdminCreated => "admin.created",
+            Action::AdminRoleUpdated => "admin.role_updated",
+            Action::AdminPasswordUpdated => "admin.password_updated",
+            Action::AdminDeleted => "admin.deleted",
+            Action::TotpEnabled => "totp.enabled",
+            Action::TotpDisabled => "totp.disabled",
+            Action::ApiTokenCreated => "api_token.created",
+            Action::ApiTokenRevoked => "api_token.revoked",
+        }
+    }
+}
+
+// Snapshot of an admin for before/after values. Never includes secrets such
+// as the password hash or the TOTP secret.
+#[derive(Serialize, Debug)]
+pub struct AdminSnapshot {
+    pub username: String,
+    pub role: String,
+    pub totp_enabled: bool,
+}
+
+impl AdminSnapshot {
+    pub fn from(a: &model::Admin) -> Self {
+        Self {
+            username: a.username.clone(),
+            role: String::from(a.role.as_str()),
+            totp_enabled: a.totp_enabled,
+        }
+    }
+}
+
+// Actor for changes made from the command line rather than the admin pages.
+pub const CLI_ACTOR: &'static str = "(cli)";
+
+// Actor for answers unpublished automatically after visitor reports.
+pub const REPORTS_ACTOR: &'static str = "(reports)";
+
+fn to_json<T: Serialize>(value: Option<&T>) -> Option<String> {
+    value.map(|v| serde_json::to_string(v).expect("Failed to serialize audit value."))
+}
+
+// before/after are snapshots of the target, stored as JSON.
+pub fn record<B: Serialize, A: Serialize>(
+    repo: &model::Repository,
+    actor: &str,
+    action: Action,
+    target_id: &str,
+    before: Option<&B>,
+    after: Option<&A>,
+) {
+    repo.store_audit_event(
+        String::from(actor),
+        String::from(action.name()),
+        String::from(target_id),
+        to_json(before),
+        to_json(after),
+    );
+}
@@ -0,0 +1,174 @@
+use chrono::prelude::*;
+use hex;
+use rand::Rng;
+use sha2::{Digest, Sha256};
+use signer::Signer;
+use std::collections::HashMap;
+use std::env;
+use std::sync::Mutex;
+
+const DEFAULT_DIFFICULTY: u32 = 16;
+// long enough to write a question after the page is loaded
+const CHALLENGE_TTL_SECS: i64 = 60 * 60;
+const SIGNING_PURPOSE: &'static str = "challenge";
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ChallengeMode {
+    Off,
+    // every question needs a solved challenge
+    Always,
+    // only senders the rate limiter finds suspicious need one
+    Suspicious,
+}
+
+// A hashcash-style proof of work: the browser searches for a nonce such that
+// SHA-256("<token>:<nonce>") starts with `difficulty` zero bits.
+#[derive(Debug, Clone)]
+pub struct Challenge {
+    pub token: String,
+    pub difficulty: u32,
+}
+
+pub struct Challenger {
+    signer: Signer,
+    mode: ChallengeMode,
+    difficulty: u32,
+    // solved tokens and when they expire, so each one is accepted only once
+    spent: Mutex<HashMap<String, i64>>,
+}
+
+#[test]
+fn verify_at_test() {
+    let challenger = Challenger::new(Signer::new(b"secret".to_vec()), ChallengeMode::Always, 4);
+    let challenge = challenger.issue_at(1000).unwrap();
+    let nonce = (0..)
+        .map(|n: u64| n.to_string())
+        .find(|nonce| leading_zero_bits(&work(&challenge.token, nonce)) >= 4)
+        .unwrap();
+    assert!(challenger.verify_at(&challenge.token, &nonce, 1000));
+    // expired
+    assert!(!challenger.verify_at(&challenge.token, &nonce, 1000 + CHALLENGE_TTL_SECS + 1));
+    // not signed by the server
+    let forged = challenge.token.replacen(".4.", ".0.", 1);
+    assert!(!challenger.verify_at(&forged, &nonce, 1000));
+}
+
+#[test]
+fn verify_at_spent_test() {
+    let challenger = Challenger::new(Signer::new(b"secret".to_vec()), ChallengeMode::Always, 4);
+    let challenge = challenger.issue_at(1000).unwrap();
+    let nonces = (0..)
+        .map(|n: u64| n.to_string())
+        .filter(|nonce| leading_zero_bits(&work(&challenge.token, nonce)) >= 4)
+        .take(2)
+        .collect::<Vec<_>>();
+    assert!(challenger.verify_at(&challenge.token, &nonces[0], 1000));
+    // a solved token can not be submitted again, even with another nonce
+    assert!(!challenger.verify_at(&challenge.token, &nonces[0], 1001));
+    assert!(!challenger.verify_at(&challenge.token, &nonces[1], 1001));
+    // but a fresh one can
+    let challenge = challenger.issue_at(1000).unwrap();
+    let nonce = (0..)
+        .map(|n: u64| n.to_string())
+        .find(|nonce| leading_zero_bits(&work(&challenge.token, nonce)) >= 4)
+        .unwrap();
+    assert!(challenger.verify_at(&challenge.token, &nonce, 1001));
+}
+
+#[test]
+fn leading_zero_bits_test() {
+    assert_eq!(leading_zero_bits(&[0xff]), 0);
+    assert_eq!(leading_zero_bits(&[0x00, 0x10]), 11);
+    assert_eq!(leading_zero_bits(&[0x00, 0x00]), 16);
+}
+
+impl Challenger {
+    pub fn new(signer: Signer, mode: ChallengeMode, difficulty: u32) -> Self {
+        Self {
+            signer: signer,
+            mode: mode,
+            difficulty: difficulty,
+            spent: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn from_env(signer: Signer) -> Self {
+        let mode = match env::var("QUESTION_CHALLENGE_MODE") {
+            Ok(ref mode) if mode == "always" => ChallengeMode::Always,
+            Ok(ref mode) if mode == "suspicious" => ChallengeMode::Suspicious,
+            Ok(ref mode) if mode != "off" => {
+                panic!("Unknown QUESTION_CHALLENGE_MODE: {}", mode)
+            }
+            _ => ChallengeMode::Off,
+        };
+        let difficulty = env::var("QUESTION_CHALLENGE_DIFFICULTY")
+            .ok()
+            .and_then(|d| d.parse().ok())
+            .unwrap_or(DEFAULT_DIFFICULTY);
+        Self::new(signer, mode, difficulty)
+    }
+
+    pub fn mode(&self) -> ChallengeMode {
+        self.mode
+    }
+
+    // None if challenges are disabled.
+    pub fn issue(&self) -> Option<Challenge> {
+        self.issue_at(Utc::now().timestamp())
+    }
+
+    fn issue_at(&self, unixtime: i64) -> Option<Challenge> {
+        if self.mode == ChallengeMode::Off {
+            return None;
+        }
+        let salt = hex::encode(rand::thread_rng().gen::<[u8; 8]>());
+        let payload = format!("{}.{}.{}", unixtime + CHALLENGE_TTL_SECS, self.difficulty, salt);
+        Some(Challenge {
+            token: self.signer.sign(SIGNING_PURPOSE, &payload),
+            difficulty: self.difficulty,
+        })
+    }
+
+    // Solved tokens are remembered in memory until they expire, so each
+    // challenge pays for a single submission. This is the only state kept:
+    // with several server processes, a token could be used once in each.
+    pub fn verify(&self, token: &str, nonce: &str) -> bool {
+        self.verify_at(token, nonce, Utc::now().timestamp())
+    }
+
+    fn verify_at(&self, token: &str, nonce: &str, unixtime: i64) -> bool {
+        let payload = match self.signer.verify(SIGNING_PURPOSE, token) {
+            Some(payload) => payload,
+            None => return false,
+        };
+        let mut fields = payload.split('.');
+        let expires_at = fields.next().and_then(|f| f.parse::<i64>().ok());
+        let difficulty = fields.next().and_then(|f| f.parse::<u32>().ok());
+        let (expires_at, difficulty) = match (expires_at, difficulty) {
+            (Some(expires_at), Some(difficulty)) => (expires_at, difficulty),
+            _ => return false,
+        };
+        if unixtime > expires_at || leading_zero_bits(&work(token, nonce)) < difficulty {
+            return false;
+        }
+
+        let mut spent = self.spent.lock().unwrap();
+        spent.retain(|_, expires_at| unixtime <= *expires_at);
+        spent.insert(String::from(token), expires_at).is_none()
+    }
+}
+
+fn work(token: &str, nonce: &str) -> Vec<u8> {
+    Sha256::digest(format!("{}:{}", token, nonce).as_bytes()).to_vec()
+}
+
+fn leading_zero_bits(hash: &[u8]) -> u32 {
+    let mut bits = 0;
+    for byte in hash {
+        bits += byte.leading_zeros();
+        if *byte != 0 {
+            break;
+        }
+    }
+    bits
+}
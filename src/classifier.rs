@@ -0,0 +1,108 @@
+use std::collections::HashMap;
+use std::env;
+use utils;
+
+// Scores are not shown until both labels have this many questions.
+pub const MIN_DOCUMENTS: i32 = 5;
+
+// Hiding a question labels it as spam, answering it labels it as ham.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Label {
+    Spam,
+    Ham,
+}
+
+impl Label {
+    pub fn parse(s: &str) -> Option<Label> {
+        match s {
+            "spam" => Some(Label::Spam),
+            "ham" => Some(Label::Ham),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Label::Spam => "spam",
+            Label::Ham => "ham",
+        }
+    }
+}
+
+// Character bigrams of the normalized body, without duplicates. Bigrams work
+// without word segmentation, which Japanese text would otherwise need.
+pub fn features(body: &str) -> Vec<String> {
+    let chars = utils::normalize_for_matching(body)
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect::<Vec<_>>();
+    let mut features = chars
+        .windows(2)
+        .map(|w| w.iter().collect::<String>())
+        .collect::<Vec<_>>();
+    features.sort();
+    features.dedup();
+    features
+}
+
+#[test]
+fn features_test() {
+    assert_eq!(features("ばかばか"), vec!["カバ", "バカ"]);
+    assert_eq!(features("A b"), vec!["ab"]);
+    assert!(features("a").is_empty());
+}
+
+// Probability that the body is spam, by naive Bayes over the features that
+// appear in it. `counts` maps a feature to the numbers of spam and ham
+// questions which had it. Add-one smoothing keeps unseen features neutral.
+pub fn score(
+    features: &[String],
+    counts: &HashMap<String, (i32, i32)>,
+    spam_documents: i32,
+    ham_documents: i32,
+) -> f64 {
+    let (spam_documents, ham_documents) = (spam_documents as f64, ham_documents as f64);
+    let mut log_odds = (spam_documents / ham_documents).ln();
+    for feature in features {
+        let (spam, ham) = counts.get(feature).cloned().unwrap_or((0, 0));
+        let p_spam = (spam as f64 + 1.0) / (spam_documents + 2.0);
+        let p_ham = (ham as f64 + 1.0) / (ham_documents + 2.0);
+        log_odds += (p_spam / p_ham).ln();
+    }
+    1.0 / (1.0 + (-log_odds).exp())
+}
+
+#[test]
+fn score_test() {
+    let mut counts = HashMap::new();
+    counts.insert(String::from("バカ"), (9, 0));
+    counts.insert(String::from("好キ"), (0, 9));
+    let spam = score(&features("ばか"), &counts, 10, 10);
+    let ham = score(&features("好き"), &counts, 10, 10);
+    let unknown = score(&features("天気"), &counts, 10, 10);
+    assert!(spam > 0.9);
+    assert!(ham < 0.1);
+    assert!((unknown - 0.5).abs() < 1e-9);
+}
+
+pub struct Settings {
+    // hide new questions scored at or above this, if set
+    pub auto_hide_threshold: Option<f64>,
+}
+
+impl Settings {
+    pub fn from_env() -> Self {
+        Self {
+            auto_hide_threshold: env::var("SPAM_AUTO_HIDE_THRESHOLD")
+                .ok()
+                .and_then(|threshold| threshold.parse().ok()),
+        }
+    }
+
+    pub fn should_hide(&self, spam_score: Option<f64>) -> bool {
+        match (self.auto_hide_threshold, spam_score) {
+            (Some(threshold), Some(score)) => score >= threshold,
+            _ => false,
+        }
+    }
+}
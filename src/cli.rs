@@ -0,0 +1,85 @@
+use audit;
+use model;
+use password;
+use rpassword;
+use std::env;
+use std::process;
+
+const USAGE: &'static str = "Usage:
+    reing                                 Launch the web server
+    reing set-admin-password <username>   Set or rotate the password of an admin
+                                          (creates an owner if the admin does not exist)";
+
+pub fn run(args: &[String], pool: model::DieselPool) {
+    let repo = model::Repository::new(pool.get().expect("Failed to get connection."));
+    match args.iter().map(|arg| arg.as_str()).collect::<Vec<_>>().as_slice() {
+        ["set-admin-password", username] => set_admin_password(&repo, username),
+        _ => {
+            eprintln!("{}", USAGE);
+            process::exit(1);
+        }
+    }
+}
+
+// Deployments from before the admins table logged in with ADMIN_USERNAME and
+// ADMIN_PASSWORD. Creates that owner on the first start so that they are not
+// locked out; does nothing once any admin exists.
+pub fn seed_admin_from_env(repo: &model::Repository) {
+    let (username, password) = match (env::var("ADMIN_USERNAME"), env::var("ADMIN_PASSWORD")) {
+        (Ok(username), Ok(password)) => (username, password),
+        _ => return,
+    };
+    if username.is_empty() || password.is_empty() || !repo.admins().is_empty() {
+        return;
+    }
+
+    repo.create_admin(username.clone(), password::hash(&password), model::Role::Owner);
+    audit::record(
+        repo,
+        audit::CLI_ACTOR,
+        audit::Action::AdminCreated,
+        &username,
+        None::<&audit::AdminSnapshot>,
+        repo.find_admin(&username).map(|a| audit::AdminSnapshot::from(&a)).as_ref(),
+    );
+    println!("Owner {} has been created from ADMIN_USERNAME and ADMIN_PASSWORD.", username);
+}
+
+fn set_admin_password(repo: &model::Repository, username: &str) {
+    let password = rpassword::read_password_from_tty(Some("New password: "))
+        .expect("Failed to read password.");
+    let confirmation = rpassword::read_password_from_tty(Some("Confirm password: "))
+        .expect("Failed to read password.");
+    if password.is_empty() || password != confirmation {
+        eprintln!("Passwords are empty or do not match.");
+        process::exit(1);
+    }
+
+    // password hashes are never written to the audit log
+    match repo.find_admin(username) {
+        Some(_) => {
+            repo.update_admin_password(username, password::hash(&password));
+            audit::record(
+                repo,
+                audit::CLI_ACTOR,
+                audit::Action::AdminPasswordUpdated,
+                username,
+                None::<&audit::AdminSnapshot>,
+                None::<&audit::AdminSnapshot>,
+            );
+            println!("Password for {} has been updated.", username);
+        }
+        None => {
+            repo.create_admin(String::from(username), password::hash(&password), model::Role::Owner);
+            audit::record(
+                repo,
+                audit::CLI_ACTOR,
+                audit::Action::AdminCreated,
+                username,
+                None::<&audit::AdminSnapshot>,
+                repo.find_admin(username).map(|a| audit::AdminSnapshot::from(&a)).as_ref(),
+            );
+            println!("Owner {} has been created.", username);
+        }
+    }
+}
@@ -2,30 +2,28 @@
 
 pub mod schema;
 
-use self::schema::{questions, answers};
+use self::schema::{questions, answers, answer_reports, admins, admin_recovery_codes, api_tokens, audit_events, blocked_senders, classifier_documents, classifier_tokens, content_rules, cross_posts, spam_counters, webhook_deliveries};
 use chrono::prelude::*;
 
 #[derive(Insertable)]
 #[table_name="questions"]
 pub struct NewQuestion {
     pub body: String,
+    pub sender_hash: Option<String>,
+    pub spam_score: Option<f64>,
+    pub status: String,
 }
 
 #[derive(Queryable, Debug, Clone)]
 pub struct Question {
     pub id: i32,
     pub body: String,
-    pub hidden: bool,
-    pub created_at: DateTime<Utc>,
-}
-
-#[derive(AsChangeset, Identifiable)]
-#[table_name = "questions"]
-pub struct QuestionForm {
-    pub id: i32,
-    pub body: String,
-    pub hidden: bool,
     pub created_at: DateTime<Utc>,
+    pub sender_hash: Option<String>,
+    pub received_count: i32,
+    pub spam_score: Option<f64>,
+    pub trained_label: Option<String>,
+    pub status: String,
 }
 
 #[derive(Insertable)]
@@ -41,4 +39,225 @@ pub struct Answer {
     pub question_id: i32,
     pub body: String,
     pub created_at: DateTime<Utc>,
+    pub published: bool,
+}
+
+#[derive(Insertable)]
+#[table_name="answer_reports"]
+pub struct NewAnswerReport {
+    pub answer_id: i32,
+    pub reason: String,
+    pub reporter_hash: String,
+}
+
+#[derive(Queryable, Debug, Clone)]
+pub struct AnswerReport {
+    pub id: i32,
+    pub answer_id: i32,
+    pub reason: String,
+    pub reporter_hash: String,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(AsChangeset, Identifiable)]
+#[table_name = "answers"]
+pub struct AnswerForm {
+    pub id: i32,
+    pub question_id: i32,
+    pub body: String,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Insertable)]
+#[table_name="webhook_deliveries"]
+pub struct NewWebhookDelivery {
+    pub event: String,
+    pub url: String,
+    pub payload: String,
+    pub status_code: Option<i32>,
+    pub error: Option<String>,
+}
+
+#[derive(Queryable, Debug, Clone)]
+pub struct WebhookDelivery {
+    pub id: i32,
+    pub event: String,
+    pub url: String,
+    pub payload: String,
+    pub status_code: Option<i32>,
+    pub error: Option<String>,
+    pub delivered_at: DateTime<Utc>,
+}
+
+#[derive(Insertable, AsChangeset)]
+#[table_name="cross_posts"]
+#[changeset_options(treat_none_as_null = "true")]
+pub struct NewCrossPost {
+    pub answer_id: i32,
+    pub service: String,
+    pub remote_id: Option<String>,
+    pub remote_url: Option<String>,
+    pub error: Option<String>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Queryable, Debug, Clone)]
+pub struct CrossPost {
+    pub id: i32,
+    pub answer_id: i32,
+    pub service: String,
+    pub remote_id: Option<String>,
+    pub remote_url: Option<String>,
+    pub error: Option<String>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Insertable)]
+#[table_name="admins"]
+pub struct NewAdmin {
+    pub username: String,
+    pub password_hash: String,
+    pub role: String,
+}
+
+#[derive(Queryable, Debug, Clone)]
+pub struct Admin {
+    pub username: String,
+    pub password_hash: String,
+    pub updated_at: DateTime<Utc>,
+    pub totp_secret: Option<String>,
+    pub totp_enabled: bool,
+    pub role: String,
+    pub created_at: DateTime<Utc>,
+    pub totp_failed_attempts: i32,
+    pub totp_locked_until: Option<DateTime<Utc>>,
+    pub totp_last_step: Option<i64>,
+}
+
+#[derive(Insertable)]
+#[table_name="admin_recovery_codes"]
+pub struct NewAdminRecoveryCode {
+    pub username: String,
+    pub code_hash: String,
+}
+
+#[derive(Insertable)]
+#[table_name="audit_events"]
+pub struct NewAuditEvent {
+    pub actor: String,
+    pub action: String,
+    pub target_id: String,
+    pub before_value: Option<String>,
+    pub after_value: Option<String>,
+}
+
+#[derive(Queryable, Debug, Clone)]
+pub struct AuditEvent {
+    pub id: i32,
+    pub actor: String,
+    pub action: String,
+    pub target_id: String,
+    pub before_value: Option<String>,
+    pub after_value: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Insertable)]
+#[table_name="api_tokens"]
+pub struct NewApiToken {
+    pub username: String,
+    pub name: String,
+    pub token_hash: String,
+    pub scope: String,
+}
+
+#[derive(Queryable, Debug, Clone)]
+pub struct ApiToken {
+    pub id: i32,
+    pub username: String,
+    pub name: String,
+    pub token_hash: String,
+    pub scope: String,
+    pub created_at: DateTime<Utc>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Insertable, AsChangeset)]
+#[table_name="blocked_senders"]
+#[changeset_options(treat_none_as_null = "true")]
+pub struct NewBlockedSender {
+    pub sender_hash: String,
+    pub blocked_by: String,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Queryable, Debug, Clone)]
+pub struct BlockedSender {
+    pub sender_hash: String,
+    pub blocked_by: String,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Insertable)]
+#[table_name="content_rules"]
+pub struct NewContentRule {
+    pub pattern: String,
+    pub is_regex: bool,
+    pub action: String,
+    pub created_by: String,
+}
+
+#[derive(Queryable, Debug, Clone)]
+pub struct ContentRule {
+    pub id: i32,
+    pub pattern: String,
+    pub is_regex: bool,
+    pub action: String,
+    pub created_by: String,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Insertable)]
+#[table_name="spam_counters"]
+pub struct NewSpamCounter {
+    pub day: NaiveDate,
+    pub reason: String,
+    pub count: i32,
+}
+
+#[derive(Queryable, Debug, Clone)]
+pub struct SpamCounter {
+    pub day: NaiveDate,
+    pub reason: String,
+    pub count: i32,
+}
+
+#[derive(Insertable)]
+#[table_name="classifier_tokens"]
+pub struct NewClassifierToken {
+    pub token: String,
+    pub spam_count: i32,
+    pub ham_count: i32,
+}
+
+#[derive(Queryable, Debug, Clone)]
+pub struct ClassifierToken {
+    pub token: String,
+    pub spam_count: i32,
+    pub ham_count: i32,
+}
+
+#[derive(Insertable)]
+#[table_name="classifier_documents"]
+pub struct NewClassifierDocument {
+    pub label: String,
+    pub count: i32,
+}
+
+#[derive(Queryable, Debug, Clone)]
+pub struct ClassifierDocument {
+    pub label: String,
+    pub count: i32,
 }
@@ -1,9 +1,116 @@
+table! {
+    admin_recovery_codes (id) {
+        id -> Int4,
+        username -> Text,
+        code_hash -> Text,
+        used_at -> Nullable<Timestamptz>,
+    }
+}
+
+table! {
+    admins (username) {
+        username -> Text,
+        password_hash -> Text,
+        updated_at -> Timestamptz,
+        totp_secret -> Nullable<Text>,
+        totp_enabled -> Bool,
+        role -> Text,
+        created_at -> Timestamptz,
+        totp_failed_attempts -> Int4,
+        totp_locked_until -> Nullable<Timestamptz>,
+        totp_last_step -> Nullable<Int8>,
+    }
+}
+
+table! {
+    answer_reports (id) {
+        id -> Int4,
+        answer_id -> Int4,
+        reason -> Text,
+        reporter_hash -> Text,
+        created_at -> Timestamptz,
+    }
+}
+
 table! {
     answers (id) {
         id -> Int4,
         question_id -> Int4,
         body -> Text,
         created_at -> Timestamptz,
+        published -> Bool,
+    }
+}
+
+table! {
+    api_tokens (id) {
+        id -> Int4,
+        username -> Text,
+        name -> Text,
+        token_hash -> Text,
+        scope -> Text,
+        created_at -> Timestamptz,
+        last_used_at -> Nullable<Timestamptz>,
+        revoked_at -> Nullable<Timestamptz>,
+    }
+}
+
+table! {
+    audit_events (id) {
+        id -> Int4,
+        actor -> Text,
+        action -> Text,
+        target_id -> Text,
+        before_value -> Nullable<Text>,
+        after_value -> Nullable<Text>,
+        created_at -> Timestamptz,
+    }
+}
+
+table! {
+    blocked_senders (sender_hash) {
+        sender_hash -> Text,
+        blocked_by -> Text,
+        created_at -> Timestamptz,
+        expires_at -> Nullable<Timestamptz>,
+    }
+}
+
+table! {
+    classifier_documents (label) {
+        label -> Text,
+        count -> Int4,
+    }
+}
+
+table! {
+    classifier_tokens (token) {
+        token -> Text,
+        spam_count -> Int4,
+        ham_count -> Int4,
+    }
+}
+
+table! {
+    content_rules (id) {
+        id -> Int4,
+        pattern -> Text,
+        is_regex -> Bool,
+        action -> Text,
+        created_by -> Text,
+        created_at -> Timestamptz,
+    }
+}
+
+table! {
+    cross_posts (id) {
+        id -> Int4,
+        answer_id -> Int4,
+        service -> Text,
+        remote_id -> Nullable<Text>,
+        remote_url -> Nullable<Text>,
+        error -> Nullable<Text>,
+        updated_at -> Timestamptz,
     }
 }
 
@@ -11,14 +118,54 @@ table! {
     questions (id) {
         id -> Int4,
         body -> Text,
-        hidden -> Bool,
         created_at -> Timestamptz,
+        sender_hash -> Nullable<Text>,
+        received_count -> Int4,
+        spam_score -> Nullable<Float8>,
+        trained_label -> Nullable<Text>,
+        status -> Text,
+    }
+}
+
+table! {
+    spam_counters (day, reason) {
+        day -> Date,
+        reason -> Text,
+        count -> Int4,
+    }
+}
+
+table! {
+    webhook_deliveries (id) {
+        id -> Int4,
+        event -> Text,
+        url -> Text,
+        payload -> Text,
+        status_code -> Nullable<Int4>,
+        error -> Nullable<Text>,
+        delivered_at -> Timestamptz,
     }
 }
 
+joinable!(admin_recovery_codes -> admins (username));
+joinable!(answer_reports -> answers (answer_id));
 joinable!(answers -> questions (question_id));
+joinable!(api_tokens -> admins (username));
+joinable!(cross_posts -> answers (answer_id));
 
 allow_tables_to_appear_in_same_query!(
+    admin_recovery_codes,
+    admins,
+    answer_reports,
     answers,
+    api_tokens,
+    audit_events,
+    blocked_senders,
+    classifier_documents,
+    classifier_tokens,
+    content_rules,
+    cross_posts,
     questions,
+    spam_counters,
+    webhook_deliveries,
 );
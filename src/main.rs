@@ -1,29 +1,43 @@
 #![feature(proc_macro_hygiene, decl_macro)]
 
+extern crate argon2;
 extern crate chrono;
 extern crate dotenv;
+extern crate hex;
+extern crate hmac;
+extern crate reqwest;
+extern crate sha2;
 extern crate uuid;
 #[macro_use]
 extern crate rocket;
+extern crate base32;
 extern crate base64;
 extern crate rocket_contrib;
 extern crate serde;
 #[macro_use]
 extern crate serde_derive;
+extern crate serde_json;
+extern crate sha1;
 extern crate r2d2;
 extern crate r2d2_diesel;
 #[macro_use]
 extern crate diesel;
-extern crate htmlescape;
 extern crate lettre;
 extern crate lettre_email;
 extern crate log;
+#[cfg(test)]
+extern crate mockito;
+extern crate qrcode;
 extern crate rand;
+extern crate regex;
 extern crate reing_text2image;
+extern crate rpassword;
+extern crate unicode_normalization;
+extern crate unicode_segmentation;
 extern crate url;
 
 use chrono::prelude::*;
-use rocket::http::{Header, Status};
+use rocket::http::{Cookies, Status};
 use rocket::request;
 use rocket::response;
 use rocket::response::status;
@@ -35,19 +49,36 @@ use std::collections::HashMap;
 use std::env;
 use std::path::{Path, PathBuf};
 
+mod api_token;
+mod audit;
+mod challenge;
+mod classifier;
+mod cli;
 mod db;
+mod mastodon;
 mod model;
 mod notify;
+mod password;
+mod profile;
+mod rate_limit;
+mod report;
+mod sender;
+mod signer;
+mod spam;
+mod totp;
+mod twitter;
 mod utils;
 mod web;
+mod webhook;
 
-#[derive(Serialize, Debug)]
+#[derive(Serialize, Debug, Clone)]
 struct AnswerDTO {
     pub id: i32,
     pub body: String,
     pub question: QuestionDTO,
     pub created_at: DateTime<Local>,
     pub created_at_recognizable: String,
+    pub published: bool,
 }
 
 impl AnswerDTO {
@@ -57,12 +88,13 @@ impl AnswerDTO {
             body: a.body,
             created_at: a.created_at,
             created_at_recognizable: utils::recognizable_datetime(a.created_at),
+            published: a.published,
             question: QuestionDTO::from(a.question),
         }
     }
 }
 
-#[derive(Serialize, Debug)]
+#[derive(Serialize, Debug, Clone)]
 struct QuestionDTO {
     pub id: i32,
     pub body: String,
@@ -81,6 +113,59 @@ impl QuestionDTO {
     }
 }
 
+// QuestionDTO with moderation data, for admin pages only.
+#[derive(Serialize, Debug, Clone)]
+struct AdminQuestionDTO {
+    pub id: i32,
+    pub body: String,
+    pub created_at: DateTime<Local>,
+    pub created_at_recognizable: String,
+    pub status: String,
+    pub status_label: String,
+    pub received_count: i32,
+    pub spam_score_percent: Option<i32>,
+}
+
+impl AdminQuestionDTO {
+    fn from(q: model::Question) -> Self {
+        Self {
+            id: q.id,
+            body: q.body,
+            created_at: q.created_at,
+            created_at_recognizable: utils::recognizable_datetime(q.created_at),
+            status: String::from(q.status.as_str()),
+            status_label: String::from(question_status_label(q.status)),
+            received_count: q.received_count,
+            spam_score_percent: q.spam_score.map(|score| (score * 100.0).round() as i32),
+        }
+    }
+}
+
+// Settings for widget/question_form
+#[derive(Serialize, Debug)]
+struct QuestionFormDTO {
+    pub max_length: usize,
+    pub challenge_token: Option<String>,
+    pub challenge_difficulty: u32,
+    pub rendered_at_token: String,
+}
+
+impl QuestionFormDTO {
+    fn new(
+        limits: &model::LengthLimits,
+        challenger: &challenge::Challenger,
+        spam_trap: &spam::SpamTrap,
+    ) -> Self {
+        let challenge = challenger.issue();
+        Self {
+            max_length: limits.question,
+            rendered_at_token: spam_trap.rendered_at_token(),
+            challenge_difficulty: challenge.as_ref().map(|c| c.difficulty).unwrap_or(0),
+            challenge_token: challenge.map(|c| c.token),
+        }
+    }
+}
+
 /* Force ssl */
 #[get("/<path..>")]
 fn redirect_ssl(path: PathBuf, _ssl: web::guard::ForceSSL) -> response::Redirect {
@@ -112,6 +197,8 @@ struct IndexDTO {
     pub site_url: String,
     pub next_page: Option<i64>,
     pub prev_page: Option<i64>,
+    pub question_form: QuestionFormDTO,
+    pub csrf_token: String,
 }
 
 #[derive(Serialize, Debug)]
@@ -120,6 +207,15 @@ struct ProfileDTO {
     pub image_url: String,
 }
 
+impl ProfileDTO {
+    fn from(p: profile::Profile) -> Self {
+        Self {
+            username: p.name,
+            image_url: p.image_url,
+        }
+    }
+}
+
 #[test]
 fn next_prev_page_test() {
     assert!((None, Some(1)) == next_prev_page(0));
@@ -141,16 +237,27 @@ fn next_prev_page(current_page: i64) -> (Option<i64>, Option<i64>) {
 
 const ANSWER_COUNT_PER_PAGE: i64 = 30;
 #[get("/")]
-fn index(repo: web::guard::Repository, profile: State<UserProfile>) -> Template {
+fn index(
+    repo: web::guard::Repository,
+    profile: State<profile::UserProfile>,
+    limits: State<model::LengthLimits>,
+    challenger: State<challenge::Challenger>,
+    spam_trap: State<spam::SpamTrap>,
+    csrf: web::csrf::CsrfToken,
+) -> Template {
     let page = 0;
-    index_with_page(repo, profile, page)
+    index_with_page(repo, profile, page, limits, challenger, spam_trap, csrf)
 }
 
 #[get("/page/<page>")]
 fn index_with_page(
     repo: web::guard::Repository,
-    profile: State<UserProfile>,
+    profile: State<profile::UserProfile>,
     page: i64,
+    limits: State<model::LengthLimits>,
+    challenger: State<challenge::Challenger>,
+    spam_trap: State<spam::SpamTrap>,
+    csrf: web::csrf::CsrfToken,
 ) -> Template {
     let offset = page * ANSWER_COUNT_PER_PAGE;
     let answer_dtos = repo
@@ -160,14 +267,13 @@ fn index_with_page(
         .collect::<Vec<_>>();
     let (next_page, prev_page) = next_prev_page(page);
     let context = IndexDTO {
-        profile: ProfileDTO {
-            username: profile.clone().name,
-            image_url: String::from("/static/image/profile.jpg"),
-        },
+        profile: ProfileDTO::from(profile.get()),
         answers: answer_dtos,
         site_url: format!("https://{}/", env::var("APPLICATION_DOMAIN").unwrap()),
         prev_page: prev_page,
         next_page: next_page,
+        question_form: QuestionFormDTO::new(&limits, &challenger, &spam_trap),
+        csrf_token: csrf.0,
     };
     Template::render("index", &context)
 }
@@ -178,23 +284,32 @@ struct SearchDTO {
     pub search_results: Vec<AnswerDTO>,
     pub site_url: String,
     pub query: String,
+    pub question_form: QuestionFormDTO,
+    pub csrf_token: String,
 }
 
 #[get("/search?<query>")]
-fn search(repo: web::guard::Repository, profile: State<UserProfile>, query: String) -> Template {
+fn search(
+    repo: web::guard::Repository,
+    profile: State<profile::UserProfile>,
+    query: String,
+    limits: State<model::LengthLimits>,
+    challenger: State<challenge::Challenger>,
+    spam_trap: State<spam::SpamTrap>,
+    csrf: web::csrf::CsrfToken,
+) -> Template {
     let answer_dtos = repo
         .search_answers(query.clone())
         .into_iter()
         .map(|a| AnswerDTO::from(a))
         .collect::<Vec<_>>();
     let context = SearchDTO {
-        profile: ProfileDTO {
-            username: profile.clone().name,
-            image_url: String::from("/static/image/profile.jpg"),
-        },
+        profile: ProfileDTO::from(profile.get()),
         search_results: answer_dtos,
         site_url: format!("https://{}/", env::var("APPLICATION_DOMAIN").unwrap()),
         query: query,
+        question_form: QuestionFormDTO::new(&limits, &challenger, &spam_trap),
+        csrf_token: csrf.0,
     };
     Template::render("search", &context)
 }
@@ -204,22 +319,144 @@ fn search(repo: web::guard::Repository, profile: State<UserProfile>, query: Stri
 #[derive(FromForm)]
 struct PostQuestionForm {
     body: String,
+    // filled by question_challenge.js
+    challenge_token: Option<String>,
+    challenge_nonce: Option<String>,
+    // honeypot, hidden from people
+    website: Option<String>,
+    // signed by spam::SpamTrap
+    rendered_at: Option<String>,
 }
 
 #[derive(Serialize, Debug)]
 struct PostQuestionFailedDTO {
     reason: String,
+    question_form: QuestionFormDTO,
+    csrf_token: String,
+}
+
+fn post_question_failed(
+    status: Status,
+    reason: &str,
+    question_form: QuestionFormDTO,
+    csrf: web::csrf::CsrfToken,
+) -> status::Custom<Template> {
+    let context = PostQuestionFailedDTO {
+        reason: String::from(reason),
+        question_form: question_form,
+        csrf_token: csrf.0,
+    };
+    status::Custom(status, Template::render("question/post_failed", &context))
 }
 
 #[post("/questions", data = "<params>")]
 fn post_question(
     repo: web::guard::Repository,
-    params: request::Form<PostQuestionForm>,
-) -> Result<response::Redirect, Template> {
-    match repo.store_question(params.body.clone()) {
+    notifier: State<notify::Notifier>,
+    sender_hasher: State<sender::SenderHasher>,
+    rate_limiter: State<rate_limit::RateLimiter>,
+    block_mode: State<sender::BlockMode>,
+    limits: State<model::LengthLimits>,
+    challenger: State<challenge::Challenger>,
+    spam_trap: State<spam::SpamTrap>,
+    classifier_settings: State<classifier::Settings>,
+    client_ip: web::guard::ClientIP,
+    params: web::csrf::CsrfForm<PostQuestionForm>,
+    csrf: web::csrf::CsrfToken,
+) -> Result<response::Redirect, status::Custom<Template>> {
+    let question_form = || QuestionFormDTO::new(&limits, &challenger, &spam_trap);
+    let website = params.website.as_ref().map_or("", |w| w.as_str());
+    let trapped = match spam_trap.check(website, params.rendered_at.as_ref().map(|r| r.as_str())) {
+        Ok(()) => false,
+        Err(err) => match err.counter_name() {
+            // looks accepted, so that bots do not learn what went wrong
+            Some(reason) => {
+                repo.increment_spam_counter(reason);
+                true
+            }
+            None => {
+                return Err(post_question_failed(
+                    Status::Forbidden,
+                    "フォームの有効期限が切れました。ページを読み込み直してからもう一度お試しください",
+                    question_form(),
+                    csrf,
+                ))
+            }
+        },
+    };
+
+    let sender_hash = sender_hasher.hash(&client_ip.address());
+    let challenge_required = match challenger.mode() {
+        challenge::ChallengeMode::Off => false,
+        challenge::ChallengeMode::Always => true,
+        challenge::ChallengeMode::Suspicious => rate_limiter.is_suspicious(&sender_hash),
+    };
+    if !rate_limiter.check(&sender_hash) {
+        return Err(post_question_failed(
+            Status::TooManyRequests,
+            "短時間に質問を送りすぎです。しばらく待ってからもう一度お試しください",
+            question_form(),
+            csrf,
+        ));
+    }
+
+    if challenge_required {
+        let solved = match (&params.challenge_token, &params.challenge_nonce) {
+            (Some(token), Some(nonce)) => challenger.verify(token, nonce),
+            _ => false,
+        };
+        if !solved {
+            return Err(post_question_failed(
+                Status::Forbidden,
+                "送信前の確認に失敗しました。JavaScriptを有効にしてもう一度お試しください",
+                question_form(),
+                csrf,
+            ));
+        }
+    }
+
+    let previous_hash = sender_hasher.previous_hash(&client_ip.address());
+    let block = repo.active_sender_block(&[sender_hash.clone(), previous_hash]);
+    if let Some(ref block) = block {
+        // keep the block across the rotation of the hash
+        if block.sender_hash != sender_hash {
+            repo.move_sender_block(&block.sender_hash, &sender_hash);
+        }
+        if *block_mode == sender::BlockMode::Reject {
+            return Err(post_question_failed(
+                Status::Forbidden,
+                "この送信元からの質問は受け付けていません",
+                question_form(),
+                csrf,
+            ));
+        }
+    }
+
+    // hidden below whatever the body is, so never merged into another question
+    let merge_duplicates = block.is_none() && !trapped;
+    match repo.store_question(
+        params.body.clone(),
+        Some(sender_hash),
+        limits.question,
+        merge_duplicates,
+    ) {
         Ok(question) => {
             let question_id = question.id;
-            notify::send_email(question);
+            let question = if !question.status.is_visible() {
+                question
+            } else if block.is_some() {
+                // shadow mode for blocked senders: looks accepted to the sender
+                repo.set_question_status(question_id, model::QuestionStatus::Hidden)
+            } else if trapped {
+                repo.set_question_status(question_id, model::QuestionStatus::Spam)
+            } else if classifier_settings.should_hide(question.spam_score) {
+                repo.set_question_status(question_id, model::QuestionStatus::Spam)
+            } else {
+                question
+            };
+            if question.status.is_visible() {
+                notifier.send_new_question(question);
+            }
             Ok(response::Redirect::to(format!(
                 "/question/{}/after_post",
                 question_id
@@ -227,11 +464,24 @@ fn post_question(
         }
         Err(err) => match err {
             model::StoreQuestionError::BlankBody => {
-                let context = PostQuestionFailedDTO {
-                    reason: String::from("質問の内容が空です"),
-                };
-                Err(Template::render("question/post_failed", &context))
+                Err(post_question_failed(Status::Ok, "質問の内容が空です", question_form(), csrf))
             }
+            model::StoreQuestionError::TooLong { max_length } => Err(post_question_failed(
+                Status::UnprocessableEntity,
+                &format!("質問は{}文字以内で入力してください", max_length),
+                question_form(),
+                csrf,
+            )),
+            model::StoreQuestionError::Rejected(_) => Err(post_question_failed(
+                Status::UnprocessableEntity,
+                "質問に送信できない表現が含まれています",
+                question_form(),
+                csrf,
+            )),
+            // already notified when the first one arrived
+            model::StoreQuestionError::Duplicate(question) => Ok(response::Redirect::to(
+                format!("/question/{}/after_post", question.id),
+            )),
         },
     }
 }
@@ -241,16 +491,24 @@ fn post_question(
 #[derive(Serialize, Debug)]
 struct AfterPostQuestionDTO {
     pub question: QuestionDTO,
+    pub question_form: QuestionFormDTO,
+    pub csrf_token: String,
 }
 
 #[get("/question/<question_id>/after_post")]
 fn after_post_question(
     question_id: i32,
     repo: web::guard::Repository,
+    limits: State<model::LengthLimits>,
+    challenger: State<challenge::Challenger>,
+    spam_trap: State<spam::SpamTrap>,
+    csrf: web::csrf::CsrfToken,
 ) -> Result<Template, response::Redirect> {
     if let Some(question) = repo.find_question(question_id) {
         let context = AfterPostQuestionDTO {
             question: QuestionDTO::from(question),
+            question_form: QuestionFormDTO::new(&limits, &challenger, &spam_trap),
+            csrf_token: csrf.0,
         };
         Ok(Template::render("question/after_post", &context))
     } else {
@@ -281,8 +539,10 @@ fn show_question(
     repo: web::guard::Repository,
 ) -> Result<response::Redirect, status::NotFound<&'static str>> {
     match repo.find_answer_by_question_id(question_id) {
-        Some(answer) => Ok(response::Redirect::to(format!("/answer/{}", answer.id))),
-        None => Err(status::NotFound("not found")),
+        Some(ref answer) if answer.published => {
+            Ok(response::Redirect::to(format!("/answer/{}", answer.id)))
+        }
+        _ => Err(status::NotFound("not found")),
     }
 }
 
@@ -292,23 +552,27 @@ fn show_question_image(
     repo: web::guard::Repository,
 ) -> Result<response::NamedFile, status::NotFound<&'static str>> {
     match repo.find_answer_by_question_id(question_id) {
-        Some(answer) => {
-            let question_image = reing_text2image::TextImage::new(
-                answer.question.body,
-                String::from("Reing"),
-                (0x2c, 0x36, 0x5d),
-            );
-            let tmp_filepath = format!("/tmp/{}.jpg", answer.question.id);
-            let tmp_filepath = Path::new(&tmp_filepath);
-            question_image
-                .save_image(&tmp_filepath)
-                .expect("failed to save image");
+        Some(ref answer) if answer.published => {
+            let tmp_filepath = question_image(&answer.question);
             Ok(response::NamedFile::open(tmp_filepath).unwrap())
         }
-        None => Err(status::NotFound("not found")),
+        _ => Err(status::NotFound("not found")),
     }
 }
 
+fn question_image(question: &model::Question) -> PathBuf {
+    let question_image = reing_text2image::TextImage::new(
+        question.body.clone(),
+        String::from("Reing"),
+        (0x2c, 0x36, 0x5d),
+    );
+    let tmp_filepath = PathBuf::from(format!("/tmp/{}.jpg", question.id));
+    question_image
+        .save_image(&tmp_filepath)
+        .expect("failed to save image");
+    tmp_filepath
+}
+
 #[derive(Serialize, Debug)]
 struct ShowAnswerDTO {
     pub answer: AnswerDTO,
@@ -318,6 +582,9 @@ struct ShowAnswerDTO {
     pub page_url: String,
     pub question_image_url: String,
     pub unixtime: i64, // FIXME
+    pub report_reason_max_length: usize,
+    pub question_form: QuestionFormDTO,
+    pub csrf_token: String,
 }
 
 #[get("/answer/<answer_id>")]
@@ -325,8 +592,12 @@ fn show_answer(
     answer_id: i32,
     repo: web::guard::Repository,
     app_env: State<AppEnvironment>,
+    limits: State<model::LengthLimits>,
+    challenger: State<challenge::Challenger>,
+    spam_trap: State<spam::SpamTrap>,
+    csrf: web::csrf::CsrfToken,
 ) -> Result<Template, status::NotFound<&'static str>> {
-    if let Some(answer) = repo.find_answer(answer_id) {
+    if let Some(answer) = repo.find_answer(answer_id).filter(|a| a.published) {
         let next_answer_opt = repo.find_next_answer(answer.created_at);
         let prev_answer_opt = repo.find_prev_answer(answer.created_at);
         let context = ShowAnswerDTO {
@@ -345,6 +616,9 @@ fn show_answer(
             prev_answer: prev_answer_opt.map(|a| AnswerDTO::from(a)),
             is_production: app_env.is_production,
             unixtime: Utc::now().timestamp(),
+            report_reason_max_length: model::REPORT_REASON_MAX_LENGTH,
+            question_form: QuestionFormDTO::new(&limits, &challenger, &spam_trap),
+            csrf_token: csrf.0,
         };
         Ok(Template::render("answer/show", &context))
     } else {
@@ -384,7 +658,7 @@ fn show_answer_json(
     answer_id: i32,
     repo: web::guard::Repository,
 ) -> Result<Json<ShowAnswerJSONDTO>, status::NotFound<&'static str>> {
-    if let Some(answer) = repo.find_answer(answer_id) {
+    if let Some(answer) = repo.find_answer(answer_id).filter(|a| a.published) {
         let next_answer_opt = repo.find_next_answer(answer.created_at);
         let prev_answer_opt = repo.find_prev_answer(answer.created_at);
         let context = ShowAnswerJSONDTO {
@@ -398,37 +672,419 @@ fn show_answer_json(
     return Err(status::NotFound("not found"));
 }
 
+/* POST /answer/<answer_id>/report */
+
+#[derive(FromForm)]
+struct ReportAnswerForm {
+    reason: String,
+}
+
+#[derive(Serialize, Debug)]
+struct ReportAnswerResultDTO {
+    pub answer_id: i32,
+    pub message: String,
+    pub csrf_token: String,
+}
+
+fn report_answer_result(
+    status: Status,
+    answer_id: i32,
+    message: &str,
+    csrf: web::csrf::CsrfToken,
+) -> status::Custom<Template> {
+    let context = ReportAnswerResultDTO {
+        answer_id: answer_id,
+        message: String::from(message),
+        csrf_token: csrf.0,
+    };
+    status::Custom(status, Template::render("answer/report_result", &context))
+}
+
+#[post("/answer/<answer_id>/report", data = "<params>")]
+fn report_answer(
+    answer_id: i32,
+    repo: web::guard::Repository,
+    notifier: State<notify::Notifier>,
+    sender_hasher: State<sender::SenderHasher>,
+    reports: State<report::AnswerReports>,
+    client_ip: web::guard::ClientIP,
+    params: web::csrf::CsrfForm<ReportAnswerForm>,
+    csrf: web::csrf::CsrfToken,
+) -> status::Custom<Template> {
+    // hashes rotate, so the same visitor may report again in a later period
+    let reporter_hash = sender_hasher.hash(&client_ip.address());
+    if !reports.check_rate(&reporter_hash) {
+        return report_answer_result(
+            Status::TooManyRequests,
+            answer_id,
+            "短時間に報告を送りすぎです。しばらく待ってからもう一度お試しください",
+            csrf,
+        );
+    }
+
+    let reason = params.reason.clone();
+    let (period_start, _) = sender_hasher.period_of(Utc::now());
+    match repo.report_answer(answer_id, reason.clone(), reporter_hash, period_start) {
+        Ok(report_count) => {
+            let mut answer = repo.find_answer(answer_id).unwrap();
+            if reports.should_unpublish(report_count) {
+                let before = AnswerDTO::from(answer.clone());
+                answer = repo.set_answer_published(answer_id, false).unwrap();
+                audit::record(
+                    &repo,
+                    audit::REPORTS_ACTOR,
+                    audit::Action::AnswerUnpublished,
+                    &answer_id.to_string(),
+                    Some(&before),
+                    Some(&AnswerDTO::from(answer.clone())),
+                );
+            }
+            notifier.send_answer_report(answer, reason, repo.report_count(answer_id));
+            report_answer_result(Status::Ok, answer_id, "報告を受け付けました。ありがとうございました", csrf)
+        }
+        // looks accepted, so that reporters cannot probe for earlier reports
+        Err(model::ReportAnswerError::AlreadyReported) => {
+            report_answer_result(Status::Ok, answer_id, "報告を受け付けました。ありがとうございました", csrf)
+        }
+        Err(model::ReportAnswerError::AnswerNotFound) => {
+            report_answer_result(Status::NotFound, answer_id, "回答が見つかりません", csrf)
+        }
+        Err(model::ReportAnswerError::BlankReason) => report_answer_result(
+            Status::UnprocessableEntity,
+            answer_id,
+            "報告の理由を入力してください",
+            csrf,
+        ),
+        Err(model::ReportAnswerError::TooLong { max_length }) => report_answer_result(
+            Status::UnprocessableEntity,
+            answer_id,
+            &format!("報告の理由は{}文字以内で入力してください", max_length),
+            csrf,
+        ),
+    }
+}
+
 /* GET /admin */
 
+#[derive(Serialize, Debug)]
+struct SpamCountDTO {
+    pub reason: String,
+    pub count: i64,
+}
+
+fn question_status_label(status: model::QuestionStatus) -> &'static str {
+    match status {
+        model::QuestionStatus::Pending => "確認待ち",
+        model::QuestionStatus::Inbox => "受信箱",
+        model::QuestionStatus::Hidden => "非表示",
+        model::QuestionStatus::Spam => "スパム",
+        model::QuestionStatus::Answered => "回答済み",
+    }
+}
+
+#[derive(Serialize, Debug)]
+struct QuestionStatusDTO {
+    pub name: String,
+    pub label: String,
+}
+
+impl QuestionStatusDTO {
+    fn from(status: model::QuestionStatus) -> Self {
+        Self {
+            name: String::from(status.as_str()),
+            label: String::from(question_status_label(status)),
+        }
+    }
+}
+
+#[derive(Serialize, Debug)]
+struct QuestionOrderDTO {
+    pub name: String,
+    pub label: String,
+}
+
+impl QuestionOrderDTO {
+    fn from(order: model::QuestionOrder) -> Self {
+        let label = match order {
+            model::QuestionOrder::Newest => "新しい順",
+            model::QuestionOrder::Oldest => "古い順",
+        };
+        Self {
+            name: String::from(order.as_str()),
+            label: String::from(label),
+        }
+    }
+}
+
 #[derive(Serialize, Debug)]
 struct AdminIndexDTO {
-    pub questions: Vec<QuestionDTO>,
+    // tabs
+    pub statuses: Vec<QuestionStatusDTO>,
+    pub status: String,
+    pub orders: Vec<QuestionOrderDTO>,
+    pub order: String,
+    pub questions: Vec<AdminQuestionDTO>,
+    // questions in the tab, of all pages
+    pub total: i64,
+    pub is_first_page: bool,
+    pub next_cursor: Option<String>,
+    // submissions dropped by the spam checks in the last SPAM_COUNT_DAYS days
+    pub spam_counts: Vec<SpamCountDTO>,
+    pub can_delete: bool,
+    // result of a bulk action
+    pub message: Option<String>,
+    pub csrf_token: String,
 }
 
-#[get("/admin")]
-fn admin_index(repo: web::guard::Repository, _auth: web::guard::BasicAuth) -> Template {
-    let question_dtos = repo
-        .not_answered_questions()
+const SPAM_COUNT_DAYS: i64 = 7;
+const ADMIN_QUESTION_COUNT_PER_PAGE: i64 = 50;
+
+#[get("/admin?<status>&<order>&<after>")]
+fn admin_index(
+    repo: web::guard::Repository,
+    status: Option<String>,
+    order: Option<String>,
+    after: Option<String>,
+    admin: web::guard::Admin,
+    flash: Option<request::FlashMessage>,
+    csrf: web::csrf::CsrfToken,
+) -> Template {
+    let status = status
+        .and_then(|s| model::QuestionStatus::parse(&s))
+        .unwrap_or(model::QuestionStatus::Inbox);
+    let order = order
+        .and_then(|o| model::QuestionOrder::parse(&o))
+        .unwrap_or(model::QuestionOrder::Newest);
+    // an invalid cursor falls back to the first page
+    let after = after.and_then(|a| model::QuestionCursor::parse(&a));
+    let page = repo.questions_page(&[status], order, after, ADMIN_QUESTION_COUNT_PER_PAGE);
+    let question_dtos = page
+        .questions
         .into_iter()
-        .filter(|q| !q.hidden)
-        .map(|q| QuestionDTO::from(q))
+        .map(|q| AdminQuestionDTO::from(q))
         .collect::<Vec<_>>();
+    let since = Local::today().naive_local() - chrono::Duration::days(SPAM_COUNT_DAYS - 1);
+    let spam_counts = repo
+        .spam_counts_since(since)
+        .into_iter()
+        .map(|(reason, count)| SpamCountDTO {
+            reason: reason,
+            count: count,
+        })
+        .collect();
     let context = AdminIndexDTO {
+        statuses: model::QuestionStatus::all()
+            .into_iter()
+            .map(|s| QuestionStatusDTO::from(s))
+            .collect(),
+        status: String::from(status.as_str()),
+        orders: model::QuestionOrder::all()
+            .into_iter()
+            .map(|o| QuestionOrderDTO::from(o))
+            .collect(),
+        order: String::from(order.as_str()),
         questions: question_dtos,
+        total: page.total,
+        is_first_page: after.is_none(),
+        next_cursor: page.next_cursor.map(|c| c.encode()),
+        spam_counts: spam_counts,
+        can_delete: admin.role.can_manage(),
+        message: flash.map(|f| String::from(f.msg())),
+        csrf_token: csrf.0,
     };
     Template::render("admin/index", &context)
 }
 
+/* POST /admin/questions/bulk */
+
+struct BulkQuestionsForm {
+    action: String,
+    question_ids: Vec<i32>,
+    // for block_sender, empty for an indefinite block
+    days: Option<i64>,
+    // tab to return to
+    status: Option<String>,
+}
+
+// Written by hand, since derived forms cannot collect repeated fields.
+impl<'f> request::FromForm<'f> for BulkQuestionsForm {
+    type Error = ();
+
+    fn from_form(items: &mut request::FormItems<'f>, _strict: bool) -> Result<Self, ()> {
+        let mut form = BulkQuestionsForm {
+            action: String::new(),
+            question_ids: vec![],
+            days: None,
+            status: None,
+        };
+        for item in items {
+            let value = item.value.url_decode().map_err(|_| ())?;
+            match item.key.as_str() {
+                "action" => form.action = value,
+                "question_id" => form.question_ids.push(value.parse().map_err(|_| ())?),
+                "days" => form.days = value.parse().ok(),
+                "status" => form.status = Some(value),
+                _ => {}
+            }
+        }
+        Ok(form)
+    }
+}
+
+#[post("/admin/questions/bulk", data = "<params>")]
+fn admin_bulk_questions(
+    repo: web::guard::Repository,
+    admin: web::guard::Admin,
+    params: web::csrf::CsrfForm<BulkQuestionsForm>,
+) -> response::Flash<response::Redirect> {
+    let status = params
+        .status
+        .as_ref()
+        .and_then(|s| model::QuestionStatus::parse(s))
+        .unwrap_or(model::QuestionStatus::Inbox);
+    let back = || response::Redirect::to(format!("/admin?status={}", status.as_str()));
+    if params.question_ids.is_empty() {
+        return response::Flash::error(back(), "質問が選択されていません");
+    }
+
+    let ids = &params.question_ids;
+    // one audit entry per affected question, as if moderated one by one
+    let before = ids
+        .iter()
+        .filter_map(|id| repo.find_question(*id))
+        .map(|q| (q.id, AuditQuestionDTO::from(&q)))
+        .collect::<HashMap<_, _>>();
+    let record_questions = |question_ids: &[i32], deleted: bool| {
+        for question_id in question_ids {
+            let after = if deleted {
+                None
+            } else {
+                repo.find_question(*question_id)
+                    .map(|q| AuditQuestionDTO::from(&q))
+            };
+            audit::record(
+                &repo,
+                &admin.username,
+                audit::Action::QuestionsBulkModerated,
+                &question_id.to_string(),
+                before.get(question_id),
+                after.as_ref(),
+            );
+        }
+    };
+
+    let message = match params.action.as_str() {
+        "hide" => {
+            let updated = repo.bulk_set_question_status(ids, model::QuestionStatus::Hidden);
+            record_questions(&updated, false);
+            format!("{}件の質問を非表示にしました", updated.len())
+        }
+        "spam" => {
+            let updated = repo.bulk_set_question_status(ids, model::QuestionStatus::Spam);
+            record_questions(&updated, false);
+            format!("{}件の質問をスパムにしました", updated.len())
+        }
+        "delete" => {
+            if !admin.role.can_manage() {
+                return response::Flash::error(back(), "質問を削除する権限がありません");
+            }
+            let deleted = repo.bulk_delete_questions(ids);
+            record_questions(&deleted, true);
+            format!("{}件の質問を削除しました", deleted.len())
+        }
+        "block_sender" => {
+            let expires_at = params
+                .days
+                .filter(|days| *days > 0)
+                .map(|days| Local::now() + chrono::Duration::days(days));
+            let sender_hashes = repo.bulk_block_senders(ids, &admin.username, expires_at);
+            for sender_hash in &sender_hashes {
+                audit::record(
+                    &repo,
+                    &admin.username,
+                    audit::Action::SenderBlocked,
+                    sender_hash,
+                    None::<&()>,
+                    Some(&AuditSenderBlockDTO {
+                        expires_at: expires_at.map(|d| d.to_rfc3339()),
+                    }),
+                );
+            }
+            format!("{}人の送信者をブロックしました", sender_hashes.len())
+        }
+        _ => return response::Flash::error(back(), "操作を選んでください"),
+    };
+
+    response::Flash::success(back(), message)
+}
+
 /* GET /admin/question/<question_id> */
 
+#[derive(Serialize, Debug)]
+struct AdminShowQuestionDTO {
+    pub question: AdminQuestionDTO,
+    pub twitter_enabled: bool,
+    pub can_answer: bool,
+    // questions from the same sender in the hash period of this one
+    pub other_questions_from_sender: Vec<AdminQuestionDTO>,
+    pub sender_known: bool,
+    pub sender_blocked: bool,
+    pub sender_blocked_until: Option<String>,
+    pub answer_max_length: usize,
+    pub answered: bool,
+    // statuses the question can be moved to
+    pub statuses: Vec<QuestionStatusDTO>,
+    pub error: Option<String>,
+    pub csrf_token: String,
+}
+
 #[get("/admin/question/<question_id>")]
 fn admin_show_question(
     question_id: i32,
     repo: web::guard::Repository,
-    _auth: web::guard::BasicAuth,
+    twitter: State<Option<twitter::Client>>,
+    limits: State<model::LengthLimits>,
+    sender_hasher: State<sender::SenderHasher>,
+    admin: web::guard::Admin,
+    flash: Option<request::FlashMessage>,
+    csrf: web::csrf::CsrfToken,
 ) -> Template {
     let question = repo.find_question(question_id).unwrap();
-    let context = QuestionDTO::from(question);
+    let status = question.status;
+    let (other_questions, sender_block) = match question.sender_hash {
+        Some(ref sender_hash) => (
+            repo.questions_by_sender(
+                sender_hash,
+                question.id,
+                sender_hasher.period_of(question.created_at.with_timezone(&Utc)),
+            ),
+            repo.active_sender_block(&[sender_hash.clone()]),
+        ),
+        None => (vec![], None),
+    };
+    let context = AdminShowQuestionDTO {
+        sender_known: question.sender_hash.is_some(),
+        question: AdminQuestionDTO::from(question),
+        twitter_enabled: twitter.is_some(),
+        can_answer: admin.role.can_answer(),
+        other_questions_from_sender: other_questions
+            .into_iter()
+            .map(|q| AdminQuestionDTO::from(q))
+            .collect(),
+        sender_blocked: sender_block.is_some(),
+        sender_blocked_until: sender_block
+            .and_then(|b| b.expires_at)
+            .map(|d| d.format("%Y-%m-%d %H:%M").to_string()),
+        answer_max_length: limits.answer,
+        answered: status == model::QuestionStatus::Answered,
+        statuses: model::QuestionStatus::all()
+            .into_iter()
+            .filter(|s| *s != model::QuestionStatus::Answered && *s != status)
+            .map(|s| QuestionStatusDTO::from(s))
+            .collect(),
+        error: flash.map(|f| String::from(f.msg())),
+        csrf_token: csrf.0,
+    };
     Template::render("admin/questions/show", &context)
 }
 
@@ -437,14 +1093,11 @@ fn admin_show_question(
 #[derive(FromForm)]
 struct PostAnswerForm {
     body: String,
+    tweet: bool,
 }
 
 fn twitter_intent_url(answer: model::Answer) -> String {
-    let url = format!(
-        "https://{}/answer/{}",
-        env::var("APPLICATION_DOMAIN").expect("failed to fetch environment variable"),
-        answer.id
-    );
+    let url = answer_url(&answer);
     let text = answer.body;
 
     url::form_urlencoded::Serializer::new(String::from("https://twitter.com/intent/tweet?"))
@@ -454,100 +1107,1726 @@ fn twitter_intent_url(answer: model::Answer) -> String {
         .finish()
 }
 
+#[derive(Serialize, Debug)]
+struct CrossPostDTO {
+    pub service: String,
+    pub posted: bool,
+    pub remote_url: Option<String>,
+    pub error: Option<String>,
+    pub updated_at_recognizable: String,
+}
+
+impl CrossPostDTO {
+    fn from(c: model::CrossPost) -> Self {
+        Self {
+            service: c.service,
+            posted: c.remote_id.is_some(),
+            remote_url: c.remote_url,
+            error: c.error,
+            updated_at_recognizable: utils::recognizable_datetime(c.updated_at),
+        }
+    }
+}
+
+fn answer_url(answer: &model::Answer) -> String {
+    format!(
+        "https://{}/answer/{}",
+        env::var("APPLICATION_DOMAIN").expect("failed to fetch environment variable"),
+        answer.id
+    )
+}
+
+fn cross_post_to_mastodon(
+    repo: &model::Repository,
+    mastodon: &mastodon::Client,
+    answer: &model::Answer,
+) -> model::CrossPost {
+    let text = format!("{}\n\n{}\n#reing", answer.body, answer_url(answer));
+    let image = question_image(&answer.question);
+    let result = mastodon
+        .post_status(&text, Some((image.as_path(), answer.question.body.as_str())))
+        .map(|status| (status.id.clone(), status.permalink()));
+    repo.store_cross_post(answer.id, mastodon::SERVICE_NAME, result)
+}
+
+fn cross_post_to_twitter(
+    repo: &model::Repository,
+    twitter: &twitter::Client,
+    answer: &model::Answer,
+) -> model::CrossPost {
+    let text = format!("{} {} #reing", answer.body, answer_url(answer));
+    let image = question_image(&answer.question);
+    let result = twitter
+        .post_tweet(&text, Some(image.as_path()))
+        .map(|tweet| (tweet.id_str.clone(), tweet.permalink()));
+    repo.store_cross_post(answer.id, twitter::SERVICE_NAME, result)
+}
+
+fn record_cross_post(
+    repo: &model::Repository,
+    actor: &str,
+    answer: &model::Answer,
+    cross_post: &CrossPostDTO,
+) {
+    audit::record(
+        repo,
+        actor,
+        audit::Action::AnswerCrossPosted,
+        &answer.id.to_string(),
+        None::<&CrossPostDTO>,
+        Some(cross_post),
+    );
+}
+
+// Everything needed to publish an answer, shared by the admin page and the API.
+struct AnswerPublisher<'a> {
+    repo: &'a model::Repository,
+    pool: &'a model::DieselPool,
+    webhooks: &'a webhook::Webhooks,
+    mastodon: Option<&'a mastodon::Client>,
+    twitter: Option<&'a twitter::Client>,
+    max_length: usize,
+}
+
+impl<'a> AnswerPublisher<'a> {
+    fn publish(
+        &self,
+        actor: &str,
+        question_id: i32,
+        body: String,
+        tweet: bool,
+    ) -> Result<(model::Answer, Vec<CrossPostDTO>), model::StoreAnswerError> {
+        let answer = self.repo.store_answer(question_id, body, self.max_length)?;
+        self.repo.train_classifier(question_id, classifier::Label::Ham);
+        let answer_dto = AnswerDTO::from(answer.clone());
+        audit::record(
+            self.repo,
+            actor,
+            audit::Action::AnswerCreated,
+            &answer.id.to_string(),
+            None::<&AnswerDTO>,
+            Some(&answer_dto),
+        );
+        self.webhooks
+            .deliver(self.pool, webhook::Event::AnswerCreated, &answer_dto);
+        let mut cross_posts = vec![];
+        if let Some(mastodon) = self.mastodon {
+            let cross_post = CrossPostDTO::from(cross_post_to_mastodon(self.repo, mastodon, &answer));
+            record_cross_post(self.repo, actor, &answer, &cross_post);
+            cross_posts.push(cross_post);
+        }
+        if let (true, Some(twitter)) = (tweet, self.twitter) {
+            let cross_post = CrossPostDTO::from(cross_post_to_twitter(self.repo, twitter, &answer));
+            record_cross_post(self.repo, actor, &answer, &cross_post);
+            cross_posts.push(cross_post);
+        }
+        Ok((answer, cross_posts))
+    }
+}
+
+fn store_answer_error_message(err: model::StoreAnswerError) -> String {
+    match err {
+        model::StoreAnswerError::QuestionNotFound => String::from("質問が見つかりません"),
+        model::StoreAnswerError::TooLong { max_length } => {
+            format!("回答は{}文字以内で入力してください", max_length)
+        }
+        model::StoreAnswerError::AlreadyAnswered => String::from("この質問には回答済みです"),
+    }
+}
+
+#[derive(Serialize, Debug)]
+struct AfterPostAnswerDTO {
+    pub answer_id: i32,
+    pub twitter_intent_url: String,
+    pub cross_posts: Vec<CrossPostDTO>,
+    pub csrf_token: String,
+}
+
 #[post("/admin/question/<question_id>/answer", data = "<params>")]
 fn admin_post_answer(
     question_id: i32,
     repo: web::guard::Repository,
-    params: request::Form<PostAnswerForm>,
-    _auth: web::guard::BasicAuth,
-) -> Template {
-    let answer_body = params.body.clone();
-    let answer = repo
-        .store_answer(question_id, answer_body.clone())
-        .expect("failed to post answer");
-    let mut context = HashMap::new();
-    context.insert("twitter_intent_url", twitter_intent_url(answer));
-    Template::render("admin/after_post_answer", &context)
+    pool: State<model::DieselPool>,
+    webhooks: State<webhook::Webhooks>,
+    mastodon: State<Option<mastodon::Client>>,
+    twitter: State<Option<twitter::Client>>,
+    limits: State<model::LengthLimits>,
+    params: web::csrf::CsrfForm<PostAnswerForm>,
+    answerer: web::guard::Answerer,
+    csrf: web::csrf::CsrfToken,
+) -> Result<Template, response::Flash<response::Redirect>> {
+    let publisher = AnswerPublisher {
+        repo: &repo,
+        pool: &pool,
+        webhooks: &webhooks,
+        mastodon: mastodon.inner().as_ref(),
+        twitter: twitter.inner().as_ref(),
+        max_length: limits.answer,
+    };
+    let (answer, cross_posts) = publisher
+        .publish(&answerer.0.username, question_id, params.body.clone(), params.tweet)
+        .map_err(|err| {
+            response::Flash::error(
+                response::Redirect::to(format!("/admin/question/{}", question_id)),
+                store_answer_error_message(err),
+            )
+        })?;
+    let context = AfterPostAnswerDTO {
+        answer_id: answer.id,
+        twitter_intent_url: twitter_intent_url(answer),
+        cross_posts: cross_posts,
+        csrf_token: csrf.0,
+    };
+    Ok(Template::render("admin/after_post_answer", &context))
+}
+
+/* POST /admin/question/<question_id>/hide, status */
+
+#[derive(Serialize, Debug)]
+struct AuditQuestionDTO {
+    pub id: i32,
+    pub body: String,
+    pub status: String,
+}
+
+impl AuditQuestionDTO {
+    fn from(q: &model::Question) -> Self {
+        Self {
+            id: q.id,
+            body: q.body.clone(),
+            status: String::from(q.status.as_str()),
+        }
+    }
+}
+
+enum UpdateQuestionStatusError {
+    NotFound,
+    // answered questions stay answered
+    Answered,
+}
+
+impl UpdateQuestionStatusError {
+    fn to_status(&self) -> status::Custom<&'static str> {
+        match self {
+            UpdateQuestionStatusError::NotFound => status::Custom(Status::NotFound, "not found"),
+            UpdateQuestionStatusError::Answered => {
+                status::Custom(Status::Conflict, "already answered")
+            }
+        }
+    }
 }
 
-/* POST /admin/question/<question_id>/hide */
+fn update_question_status(
+    repo: &model::Repository,
+    actor: &str,
+    question_id: i32,
+    status: model::QuestionStatus,
+) -> Result<model::Question, UpdateQuestionStatusError> {
+    let question = repo
+        .find_question(question_id)
+        .ok_or(UpdateQuestionStatusError::NotFound)?;
+    if question.status == model::QuestionStatus::Answered {
+        return Err(UpdateQuestionStatusError::Answered);
+    }
+    let before = AuditQuestionDTO::from(&question);
+    let question = repo.set_question_status(question_id, status);
+    if status == model::QuestionStatus::Hidden || status == model::QuestionStatus::Spam {
+        repo.train_classifier(question_id, classifier::Label::Spam);
+    } else {
+        // restored, so it was not spam after all
+        repo.untrain_classifier(question_id, classifier::Label::Spam);
+    }
+    audit::record(
+        repo,
+        actor,
+        audit::Action::QuestionStatusUpdated,
+        &question_id.to_string(),
+        Some(&before),
+        Some(&AuditQuestionDTO::from(&question)),
+    );
+    Ok(question)
+}
 
-#[post("/admin/question/<question_id>/hide")]
+#[post("/admin/question/<question_id>/hide", data = "<_csrf>")]
 fn admin_hide_question(
     question_id: i32,
     repo: web::guard::Repository,
-    _auth: web::guard::BasicAuth,
-) -> response::Redirect {
-    let mut question = repo.find_question(question_id).unwrap();
-    question.hidden = true;
-    repo.update_question(question);
-
-    response::Redirect::to("/admin")
+    admin: web::guard::Admin,
+    _csrf: web::csrf::CsrfForm<web::csrf::NoFields>,
+) -> Result<response::Redirect, status::Custom<&'static str>> {
+    update_question_status(&repo, &admin.username, question_id, model::QuestionStatus::Hidden)
+        .map_err(|err| err.to_status())?;
+    Ok(response::Redirect::to("/admin"))
 }
 
-/* Force login */
+#[derive(FromForm)]
+struct QuestionStatusForm {
+    status: String,
+}
 
-struct RequireLogin();
+#[post("/admin/question/<question_id>/status", data = "<params>")]
+fn admin_update_question_status(
+    question_id: i32,
+    repo: web::guard::Repository,
+    admin: web::guard::Admin,
+    params: web::csrf::CsrfForm<QuestionStatusForm>,
+) -> Result<response::Redirect, status::Custom<&'static str>> {
+    // answered only by posting an answer
+    let status = match model::QuestionStatus::parse(&params.status) {
+        Some(model::QuestionStatus::Answered) | None => {
+            return Err(status::Custom(Status::UnprocessableEntity, "invalid status"))
+        }
+        Some(status) => status,
+    };
+    update_question_status(&repo, &admin.username, question_id, status)
+        .map_err(|err| err.to_status())?;
 
-impl<'r> response::Responder<'r> for RequireLogin {
-    fn respond_to(self, _req: &Request) -> Result<response::Response<'r>, Status> {
-        response::Response::build()
-            .status(Status::Unauthorized)
-            .header(Header::new(
-                "WWW-Authenticate",
-                "Basic realm=\"SECRET AREA\"",
-            ))
-            .ok()
-    }
+    Ok(response::Redirect::to(format!("/admin/question/{}", question_id)))
 }
 
-#[catch(401)]
-fn unauthorized(_req: &Request) -> RequireLogin {
-    RequireLogin()
+/* POST /admin/question/<question_id>/block_sender, unblock_sender */
+
+#[derive(FromForm)]
+struct BlockSenderForm {
+    // empty for an indefinite block
+    days: Option<i64>,
 }
 
-#[derive(Clone)]
-struct UserProfile {
-    pub name: String,
+#[derive(Serialize, Debug)]
+struct AuditSenderBlockDTO {
+    pub expires_at: Option<String>,
 }
 
-#[derive(Clone)]
-struct AppEnvironment {
-    pub is_production: bool,
+fn find_sender_hash(
+    repo: &model::Repository,
+    question_id: i32,
+) -> Result<String, status::NotFound<&'static str>> {
+    repo.find_question(question_id)
+        .and_then(|q| q.sender_hash)
+        .ok_or(status::NotFound("not found"))
 }
 
-fn database_url() -> String {
-    let user = env::var("POSTGRES_USER").expect("POSTGRES_USER not defined");
-    let pw = env::var("POSTGRES_PASSWORD").expect("POSTGRES_PASSWORD not defined");
-    let host = env::var("POSTGRES_HOST").expect("POSTGRES_HOST not defined");
-    let db = env::var("POSTGRES_DB").expect("POSTGRES_DB not defined");
-    let database_url = format!(
-        "postgresql://{}:{}@{}:5432/{}", user, pw, host, db
+#[post("/admin/question/<question_id>/block_sender", data = "<params>")]
+fn admin_block_sender(
+    question_id: i32,
+    repo: web::guard::Repository,
+    admin: web::guard::Admin,
+    params: web::csrf::CsrfForm<BlockSenderForm>,
+) -> Result<response::Redirect, status::NotFound<&'static str>> {
+    let sender_hash = find_sender_hash(&repo, question_id)?;
+    let expires_at = params
+        .days
+        .filter(|days| *days > 0)
+        .map(|days| Local::now() + chrono::Duration::days(days));
+    repo.block_sender(sender_hash.clone(), admin.username.clone(), expires_at);
+    audit::record(
+        &repo,
+        &admin.username,
+        audit::Action::SenderBlocked,
+        &sender_hash,
+        None::<&()>,
+        Some(&AuditSenderBlockDTO {
+            expires_at: expires_at.map(|d| d.to_rfc3339()),
+        }),
     );
-    database_url
+    Ok(response::Redirect::to(format!("/admin/question/{}", question_id)))
 }
 
-fn main() {
-    dotenv::dotenv().ok();
-    let manager = r2d2_diesel::ConnectionManager::<diesel::PgConnection>::new(
-        database_url(),
+#[post("/admin/question/<question_id>/unblock_sender", data = "<_csrf>")]
+fn admin_unblock_sender(
+    question_id: i32,
+    repo: web::guard::Repository,
+    admin: web::guard::Admin,
+    _csrf: web::csrf::CsrfForm<web::csrf::NoFields>,
+) -> Result<response::Redirect, status::NotFound<&'static str>> {
+    let sender_hash = find_sender_hash(&repo, question_id)?;
+    repo.unblock_sender(&sender_hash);
+    audit::record(
+        &repo,
+        &admin.username,
+        audit::Action::SenderUnblocked,
+        &sender_hash,
+        None::<&()>,
+        None::<&()>,
     );
-    let pool = r2d2::Pool::builder().max_size(15).build(manager).unwrap();
+    Ok(response::Redirect::to(format!("/admin/question/{}", question_id)))
+}
+
+/* GET /admin/answers */
+
+#[derive(Serialize, Debug)]
+struct AdminAnswersDTO {
+    pub answers: Vec<AnswerDTO>,
+    pub next_page: Option<i64>,
+    pub prev_page: Option<i64>,
+    pub csrf_token: String,
+}
+
+#[get("/admin/answers?<page>")]
+fn admin_answers(
+    repo: web::guard::Repository,
+    page: Option<i64>,
+    _admin: web::guard::Admin,
+    csrf: web::csrf::CsrfToken,
+) -> Template {
+    let page = page.unwrap_or(0);
+    let answer_dtos = repo
+        .all_answers(page * ANSWER_COUNT_PER_PAGE, ANSWER_COUNT_PER_PAGE)
+        .into_iter()
+        .map(|a| AnswerDTO::from(a))
+        .collect::<Vec<_>>();
+    let (next_page, prev_page) = next_prev_page(page);
+    let context = AdminAnswersDTO {
+        answers: answer_dtos,
+        next_page: next_page,
+        prev_page: prev_page,
+        csrf_token: csrf.0,
+    };
+    Template::render("admin/answers/index", &context)
+}
+
+/* GET /admin/answer/<answer_id> */
+
+#[derive(Serialize, Debug)]
+struct AdminShowAnswerDTO {
+    pub answer: AnswerDTO,
+    pub cross_posts: Vec<CrossPostDTO>,
+    pub mastodon_enabled: bool,
+    pub twitter_enabled: bool,
+    // already posted, so no more buttons
+    pub mastodon_posted: bool,
+    pub twitter_posted: bool,
+    pub can_answer: bool,
+    pub answer_max_length: usize,
+    pub report_count: i64,
+    pub error: Option<String>,
+    pub csrf_token: String,
+}
+
+#[get("/admin/answer/<answer_id>")]
+fn admin_show_answer(
+    answer_id: i32,
+    repo: web::guard::Repository,
+    mastodon: State<Option<mastodon::Client>>,
+    twitter: State<Option<twitter::Client>>,
+    limits: State<model::LengthLimits>,
+    admin: web::guard::Admin,
+    flash: Option<request::FlashMessage>,
+    csrf: web::csrf::CsrfToken,
+) -> Result<Template, status::NotFound<&'static str>> {
+    match repo.find_answer(answer_id) {
+        Some(answer) => {
+            let cross_post_dtos = repo
+                .cross_posts(answer.id)
+                .into_iter()
+                .map(|c| CrossPostDTO::from(c))
+                .collect::<Vec<_>>();
+            let posted = |service: &str| {
+                cross_post_dtos
+                    .iter()
+                    .any(|c| c.service == service && c.posted)
+            };
+            let context = AdminShowAnswerDTO {
+                report_count: repo.report_count(answer.id),
+                answer: AnswerDTO::from(answer),
+                mastodon_enabled: mastodon.is_some(),
+                twitter_enabled: twitter.is_some(),
+                mastodon_posted: posted(mastodon::SERVICE_NAME),
+                twitter_posted: posted(twitter::SERVICE_NAME),
+                cross_posts: cross_post_dtos,
+                can_answer: admin.role.can_answer(),
+                answer_max_length: limits.answer,
+                error: flash.map(|f| String::from(f.msg())),
+                csrf_token: csrf.0,
+            };
+            Ok(Template::render("admin/answers/show", &context))
+        }
+        None => Err(status::NotFound("not found")),
+    }
+}
+
+/* POST /admin/answer/<answer_id>/cross_post/<service> */
+
+#[post("/admin/answer/<answer_id>/cross_post/<service>", data = "<_csrf>")]
+fn admin_cross_post_answer(
+    answer_id: i32,
+    service: String,
+    repo: web::guard::Repository,
+    mastodon: State<Option<mastodon::Client>>,
+    twitter: State<Option<twitter::Client>>,
+    answerer: web::guard::Answerer,
+    _csrf: web::csrf::CsrfForm<web::csrf::NoFields>,
+) -> Result<response::Redirect, status::NotFound<&'static str>> {
+    let answer = match repo.find_answer(answer_id) {
+        Some(answer) => answer,
+        None => return Err(status::NotFound("not found")),
+    };
+    // retrying is for failed posts only
+    let posted = repo
+        .cross_posts(answer.id)
+        .into_iter()
+        .any(|c| c.service == service && c.remote_id.is_some());
+    if posted {
+        return Ok(response::Redirect::to(format!("/admin/answer/{}", answer.id)));
+    }
+    let cross_post = match (service.as_str(), mastodon.inner(), twitter.inner()) {
+        (mastodon::SERVICE_NAME, Some(mastodon), _) => {
+            cross_post_to_mastodon(&repo, mastodon, &answer)
+        }
+        (twitter::SERVICE_NAME, _, Some(twitter)) => {
+            cross_post_to_twitter(&repo, twitter, &answer)
+        }
+        _ => return Err(status::NotFound("not found")),
+    };
+    record_cross_post(&repo, &answerer.0.username, &answer, &CrossPostDTO::from(cross_post));
+
+    Ok(response::Redirect::to(format!("/admin/answer/{}", answer.id)))
+}
+
+/* POST /admin/answer/<answer_id>/edit */
+
+#[post("/admin/answer/<answer_id>/edit", data = "<params>")]
+fn admin_update_answer(
+    answer_id: i32,
+    repo: web::guard::Repository,
+    pool: State<model::DieselPool>,
+    webhooks: State<webhook::Webhooks>,
+    limits: State<model::LengthLimits>,
+    params: web::csrf::CsrfForm<PostAnswerForm>,
+    answerer: web::guard::Answerer,
+) -> Result<response::Redirect, response::Flash<response::Redirect>> {
+    let mut answer = match repo.find_answer(answer_id) {
+        Some(answer) => answer,
+        None => {
+            return Err(response::Flash::error(
+                response::Redirect::to("/admin/answers"),
+                "回答が見つかりません",
+            ))
+        }
+    };
+    let before = AnswerDTO::from(answer.clone());
+    answer.body = params.body.clone();
+    let answer = repo.update_answer(answer, limits.answer).map_err(|err| {
+        response::Flash::error(
+            response::Redirect::to(format!("/admin/answer/{}", answer_id)),
+            store_answer_error_message(err),
+        )
+    })?;
+    let after = AnswerDTO::from(answer.clone());
+    audit::record(
+        &repo,
+        &answerer.0.username,
+        audit::Action::AnswerUpdated,
+        &answer.id.to_string(),
+        Some(&before),
+        Some(&after),
+    );
+    webhooks.deliver(&pool, webhook::Event::AnswerUpdated, &after);
+
+    Ok(response::Redirect::to(format!("/admin/answer/{}", answer.id)))
+}
+
+/* POST /admin/answer/<answer_id>/delete */
+
+#[post("/admin/answer/<answer_id>/delete", data = "<_csrf>")]
+fn admin_delete_answer(
+    answer_id: i32,
+    repo: web::guard::Repository,
+    pool: State<model::DieselPool>,
+    webhooks: State<webhook::Webhooks>,
+    answerer: web::guard::Answerer,
+    _csrf: web::csrf::CsrfForm<web::csrf::NoFields>,
+) -> Result<response::Redirect, status::NotFound<&'static str>> {
+    match repo.delete_answer(answer_id) {
+        Some(answer) => {
+            let before = AnswerDTO::from(answer);
+            audit::record(
+                &repo,
+                &answerer.0.username,
+                audit::Action::AnswerDeleted,
+                &answer_id.to_string(),
+                Some(&before),
+                None::<&AnswerDTO>,
+            );
+            webhooks.deliver(&pool, webhook::Event::AnswerDeleted, &before);
+            Ok(response::Redirect::to("/admin/answers"))
+        }
+        None => Err(status::NotFound("not found")),
+    }
+}
+
+/* POST /admin/answer/<answer_id>/publish, unpublish */
+
+// Returns None if the answer does not exist.
+fn set_answer_published(
+    repo: &model::Repository,
+    actor: &str,
+    answer_id: i32,
+    published: bool,
+) -> Option<model::Answer> {
+    let before = AnswerDTO::from(repo.find_answer(answer_id)?);
+    let answer = repo.set_answer_published(answer_id, published)?;
+    let action = if published {
+        audit::Action::AnswerPublished
+    } else {
+        audit::Action::AnswerUnpublished
+    };
+    audit::record(
+        repo,
+        actor,
+        action,
+        &answer_id.to_string(),
+        Some(&before),
+        Some(&AnswerDTO::from(answer.clone())),
+    );
+    Some(answer)
+}
+
+// Publishing is for answerers, while moderators may take answers down.
+#[post("/admin/answer/<answer_id>/publish", data = "<_csrf>")]
+fn admin_publish_answer(
+    answer_id: i32,
+    repo: web::guard::Repository,
+    answerer: web::guard::Answerer,
+    _csrf: web::csrf::CsrfForm<web::csrf::NoFields>,
+) -> Result<response::Redirect, status::NotFound<&'static str>> {
+    set_answer_published(&repo, &answerer.0.username, answer_id, true)
+        .ok_or(status::NotFound("not found"))?;
+    Ok(response::Redirect::to(format!("/admin/answer/{}", answer_id)))
+}
+
+#[post("/admin/answer/<answer_id>/unpublish", data = "<_csrf>")]
+fn admin_unpublish_answer(
+    answer_id: i32,
+    repo: web::guard::Repository,
+    admin: web::guard::Admin,
+    _csrf: web::csrf::CsrfForm<web::csrf::NoFields>,
+) -> Result<response::Redirect, status::NotFound<&'static str>> {
+    set_answer_published(&repo, &admin.username, answer_id, false)
+        .ok_or(status::NotFound("not found"))?;
+    Ok(response::Redirect::to(format!("/admin/answer/{}", answer_id)))
+}
+
+/* GET /admin/reports */
+
+#[derive(Serialize, Debug)]
+struct AnswerReportDTO {
+    pub id: i32,
+    pub reason: String,
+    pub created_at_recognizable: String,
+    pub answer: AnswerDTO,
+}
+
+impl AnswerReportDTO {
+    fn from(r: model::AnswerReport) -> Self {
+        Self {
+            id: r.id,
+            reason: r.reason,
+            created_at_recognizable: utils::recognizable_datetime(r.created_at),
+            answer: AnswerDTO::from(r.answer),
+        }
+    }
+}
+
+#[derive(Serialize, Debug)]
+struct AdminAnswerReportsDTO {
+    pub reports: Vec<AnswerReportDTO>,
+    pub csrf_token: String,
+}
+
+#[get("/admin/reports")]
+fn admin_answer_reports(
+    repo: web::guard::Repository,
+    _admin: web::guard::Admin,
+    csrf: web::csrf::CsrfToken,
+) -> Template {
+    let context = AdminAnswerReportsDTO {
+        reports: repo
+            .answer_reports()
+            .into_iter()
+            .map(|r| AnswerReportDTO::from(r))
+            .collect(),
+        csrf_token: csrf.0,
+    };
+    Template::render("admin/reports/index", &context)
+}
+
+/* POST /admin/answer/<answer_id>/reports/dismiss */
+
+#[derive(Serialize, Debug)]
+struct AuditAnswerReportsDTO {
+    pub count: usize,
+}
+
+#[post("/admin/answer/<answer_id>/reports/dismiss", data = "<_csrf>")]
+fn admin_dismiss_answer_reports(
+    answer_id: i32,
+    repo: web::guard::Repository,
+    admin: web::guard::Admin,
+    _csrf: web::csrf::CsrfForm<web::csrf::NoFields>,
+) -> response::Redirect {
+    let count = repo.delete_answer_reports(answer_id);
+    if count > 0 {
+        audit::record(
+            &repo,
+            &admin.username,
+            audit::Action::AnswerReportsDismissed,
+            &answer_id.to_string(),
+            Some(&AuditAnswerReportsDTO { count: count }),
+            None::<&AuditAnswerReportsDTO>,
+        );
+    }
+    response::Redirect::to("/admin/reports")
+}
+
+/* GET /admin/webhooks */
+
+#[derive(Serialize, Debug)]
+struct WebhookDeliveryDTO {
+    pub id: i32,
+    pub event: String,
+    pub url: String,
+    pub payload: String,
+    pub status_code: Option<i32>,
+    pub error: Option<String>,
+    pub delivered_at_recognizable: String,
+}
+
+#[derive(Serialize, Debug)]
+struct AdminWebhooksDTO {
+    pub deliveries: Vec<WebhookDeliveryDTO>,
+    pub csrf_token: String,
+}
+
+const WEBHOOK_DELIVERY_COUNT: i64 = 100;
+#[get("/admin/webhooks")]
+fn admin_webhook_deliveries(
+    repo: web::guard::Repository,
+    _owner: web::guard::Owner,
+    csrf: web::csrf::CsrfToken,
+) -> Template {
+    let delivery_dtos = repo
+        .webhook_deliveries(WEBHOOK_DELIVERY_COUNT)
+        .into_iter()
+        .map(|d| WebhookDeliveryDTO {
+            id: d.id,
+            event: d.event,
+            url: d.url,
+            payload: d.payload,
+            status_code: d.status_code,
+            error: d.error,
+            delivered_at_recognizable: utils::recognizable_datetime(d.delivered_at),
+        })
+        .collect::<Vec<_>>();
+    let context = AdminWebhooksDTO {
+        deliveries: delivery_dtos,
+        csrf_token: csrf.0,
+    };
+    Template::render("admin/webhooks/index", &context)
+}
+
+/* GET /admin/admins */
+
+#[derive(Serialize, Debug)]
+struct AdminDTO {
+    pub username: String,
+    pub role: String,
+    pub totp_enabled: bool,
+}
+
+impl AdminDTO {
+    fn from(a: &model::Admin) -> Self {
+        Self {
+            username: a.username.clone(),
+            role: String::from(a.role.as_str()),
+            totp_enabled: a.totp_enabled,
+        }
+    }
+}
+
+#[derive(Serialize, Debug)]
+struct AdminAdminsDTO {
+    pub admins: Vec<AdminDTO>,
+    pub roles: Vec<String>,
+    pub current_username: String,
+    pub error: Option<String>,
+    pub csrf_token: String,
+}
+
+#[get("/admin/admins")]
+fn admin_admins(
+    repo: web::guard::Repository,
+    owner: web::guard::Owner,
+    flash: Option<request::FlashMessage>,
+    csrf: web::csrf::CsrfToken,
+) -> Template {
+    let admin_dtos = repo
+        .admins()
+        .into_iter()
+        .map(|a| AdminDTO::from(&a))
+        .collect::<Vec<_>>();
+    let context = AdminAdminsDTO {
+        admins: admin_dtos,
+        roles: model::Role::all()
+            .into_iter()
+            .map(|r| String::from(r.as_str()))
+            .collect(),
+        current_username: owner.0.username.clone(),
+        error: flash.map(|f| String::from(f.msg())),
+        csrf_token: csrf.0,
+    };
+    Template::render("admin/admins/index", &context)
+}
+
+/* POST /admin/admins */
+
+#[derive(FromForm)]
+struct NewAdminForm {
+    username: String,
+    password: String,
+    role: String,
+}
+
+#[post("/admin/admins", data = "<params>")]
+fn admin_create_admin(
+    repo: web::guard::Repository,
+    owner: web::guard::Owner,
+    params: web::csrf::CsrfForm<NewAdminForm>,
+) -> Result<response::Redirect, response::Flash<response::Redirect>> {
+    let error = |message| response::Flash::error(response::Redirect::to("/admin/admins"), message);
+    let username = params.username.trim();
+    if username.is_empty() || params.password.is_empty() {
+        return Err(error("ユーザー名とパスワードを入力してください"));
+    }
+    let role = model::Role::parse(&params.role).ok_or_else(|| error("権限が正しくありません"))?;
+    if repo.find_admin(username).is_some() {
+        return Err(error("そのユーザー名は既に使われています"));
+    }
+
+    repo.create_admin(String::from(username), password::hash(&params.password), role);
+    audit::record(
+        &repo,
+        &owner.0.username,
+        audit::Action::AdminCreated,
+        username,
+        None::<&audit::AdminSnapshot>,
+        repo.find_admin(username).map(|a| audit::AdminSnapshot::from(&a)).as_ref(),
+    );
+    Ok(response::Redirect::to("/admin/admins"))
+}
+
+/* POST /admin/admins/<username>/role */
+
+#[derive(FromForm)]
+struct AdminRoleForm {
+    role: String,
+}
+
+fn admin_error_message(err: model::AdminError) -> &'static str {
+    match err {
+        model::AdminError::LastOwner => "owner が一人もいなくなるため変更できません",
+    }
+}
+
+#[post("/admin/admins/<username>/role", data = "<params>")]
+fn admin_update_admin_role(
+    username: String,
+    repo: web::guard::Repository,
+    owner: web::guard::Owner,
+    params: web::csrf::CsrfForm<AdminRoleForm>,
+) -> Result<response::Redirect, response::Flash<response::Redirect>> {
+    let error = |message| response::Flash::error(response::Redirect::to("/admin/admins"), message);
+    let role = model::Role::parse(&params.role).ok_or_else(|| error("権限が正しくありません"))?;
+    let before = match repo.find_admin(&username) {
+        Some(admin) => audit::AdminSnapshot::from(&admin),
+        None => return Err(error("管理者が見つかりません")),
+    };
+
+    repo.update_admin_role(&username, role)
+        .map_err(|err| error(admin_error_message(err)))?;
+    audit::record(
+        &repo,
+        &owner.0.username,
+        audit::Action::AdminRoleUpdated,
+        &username,
+        Some(&before),
+        repo.find_admin(&username).map(|a| audit::AdminSnapshot::from(&a)).as_ref(),
+    );
+    Ok(response::Redirect::to("/admin/admins"))
+}
+
+/* POST /admin/admins/<username>/delete */
+
+#[post("/admin/admins/<username>/delete", data = "<_csrf>")]
+fn admin_delete_admin(
+    username: String,
+    repo: web::guard::Repository,
+    owner: web::guard::Owner,
+    _csrf: web::csrf::CsrfForm<web::csrf::NoFields>,
+) -> Result<response::Redirect, response::Flash<response::Redirect>> {
+    let error = |message| response::Flash::error(response::Redirect::to("/admin/admins"), message);
+    if username == owner.0.username {
+        return Err(error("自分自身は削除できません"));
+    }
+    let before = match repo.find_admin(&username) {
+        Some(admin) => audit::AdminSnapshot::from(&admin),
+        None => return Err(error("管理者が見つかりません")),
+    };
+
+    repo.delete_admin(&username)
+        .map_err(|err| error(admin_error_message(err)))?;
+    audit::record(
+        &repo,
+        &owner.0.username,
+        audit::Action::AdminDeleted,
+        &username,
+        Some(&before),
+        None::<&audit::AdminSnapshot>,
+    );
+    Ok(response::Redirect::to("/admin/admins"))
+}
+
+/* GET /admin/audit */
+
+#[derive(Serialize, Debug)]
+struct AuditEventDTO {
+    pub actor: String,
+    pub action: String,
+    pub target_id: String,
+    pub before_value: Option<String>,
+    pub after_value: Option<String>,
+    pub created_at: String,
+}
+
+#[derive(Serialize, Debug)]
+struct AuditActionDTO {
+    pub name: String,
+    pub legacy: bool,
+}
+
+#[derive(Serialize, Debug)]
+struct AdminAuditDTO {
+    pub events: Vec<AuditEventDTO>,
+    pub actions: Vec<AuditActionDTO>,
+    pub action: String,
+    pub from: String,
+    pub to: String,
+    pub csrf_token: String,
+}
+
+const AUDIT_EVENT_COUNT: i64 = 200;
+#[get("/admin/audit?<action>&<from>&<to>")]
+fn admin_audit_events(
+    repo: web::guard::Repository,
+    action: Option<String>,
+    from: Option<String>,
+    to: Option<String>,
+    _owner: web::guard::Owner,
+    csrf: web::csrf::CsrfToken,
+) -> Template {
+    let action = action.filter(|a| !a.is_empty());
+    let filter = model::AuditEventFilter {
+        action: action.clone(),
+        from: from.as_ref().and_then(|d| utils::parse_local_date(d)),
+        // include the whole day of `to`
+        to: to
+            .as_ref()
+            .and_then(|d| utils::parse_local_date(d))
+            .map(|d| d + chrono::Duration::days(1)),
+    };
+    let event_dtos = repo
+        .audit_events(&filter, AUDIT_EVENT_COUNT)
+        .into_iter()
+        .map(|e| AuditEventDTO {
+            actor: e.actor,
+            action: e.action,
+            target_id: e.target_id,
+            before_value: e.before_value,
+            after_value: e.after_value,
+            created_at: e.created_at.format("%Y-%m-%d %H:%M:%S").to_string(),
+        })
+        .collect::<Vec<_>>();
+    let context = AdminAuditDTO {
+        events: event_dtos,
+        actions: audit::Action::all()
+            .into_iter()
+            .map(|a| AuditActionDTO {
+                name: String::from(a.name()),
+                legacy: a.is_legacy(),
+            })
+            .collect(),
+        action: action.unwrap_or_default(),
+        from: from.unwrap_or_default(),
+        to: to.unwrap_or_default(),
+        csrf_token: csrf.0,
+    };
+    Template::render("admin/audit/index", &context)
+}
+
+/* GET /admin/rules */
+
+#[derive(Serialize, Debug)]
+struct ContentRuleDTO {
+    pub id: i32,
+    pub pattern: String,
+    pub is_regex: bool,
+    pub action: String,
+    pub created_by: String,
+    pub created_at_recognizable: String,
+}
+
+impl ContentRuleDTO {
+    fn from(r: &model::ContentRule) -> Self {
+        Self {
+            id: r.id,
+            pattern: r.pattern.clone(),
+            is_regex: r.is_regex,
+            action: String::from(r.action.as_str()),
+            created_by: r.created_by.clone(),
+            created_at_recognizable: utils::recognizable_datetime(r.created_at),
+        }
+    }
+}
+
+#[derive(Serialize, Debug)]
+struct AdminContentRulesDTO {
+    pub rules: Vec<ContentRuleDTO>,
+    pub actions: Vec<String>,
+    pub error: Option<String>,
+    pub csrf_token: String,
+}
+
+#[get("/admin/rules")]
+fn admin_content_rules(
+    repo: web::guard::Repository,
+    _admin: web::guard::Admin,
+    flash: Option<request::FlashMessage>,
+    csrf: web::csrf::CsrfToken,
+) -> Template {
+    let context = AdminContentRulesDTO {
+        rules: repo
+            .content_rules()
+            .iter()
+            .map(|r| ContentRuleDTO::from(r))
+            .collect(),
+        actions: model::RuleAction::all()
+            .into_iter()
+            .map(|a| String::from(a.as_str()))
+            .collect(),
+        error: flash.map(|f| String::from(f.msg())),
+        csrf_token: csrf.0,
+    };
+    Template::render("admin/rules/index", &context)
+}
+
+/* POST /admin/rules */
+
+#[derive(FromForm)]
+struct NewContentRuleForm {
+    pattern: String,
+    is_regex: bool,
+    action: String,
+}
+
+#[post("/admin/rules", data = "<params>")]
+fn admin_create_content_rule(
+    repo: web::guard::Repository,
+    admin: web::guard::Admin,
+    params: web::csrf::CsrfForm<NewContentRuleForm>,
+) -> Result<response::Redirect, response::Flash<response::Redirect>> {
+    let error = |message| response::Flash::error(response::Redirect::to("/admin/rules"), message);
+    let pattern = params.pattern.trim();
+    if pattern.is_empty() {
+        return Err(error("ルールの内容を入力してください"));
+    }
+    // validate the pattern as it is used for matching
+    if params.is_regex && model::rule_regex(pattern).is_none() {
+        return Err(error("正規表現が正しくありません"));
+    }
+    let action = model::RuleAction::parse(&params.action).ok_or_else(|| error("動作が正しくありません"))?;
+
+    let rule = repo.store_content_rule(
+        String::from(pattern),
+        params.is_regex,
+        action,
+        admin.username.clone(),
+    );
+    audit::record(
+        &repo,
+        &admin.username,
+        audit::Action::ContentRuleCreated,
+        &rule.id.to_string(),
+        None::<&ContentRuleDTO>,
+        Some(&ContentRuleDTO::from(&rule)),
+    );
+    Ok(response::Redirect::to("/admin/rules"))
+}
+
+/* POST /admin/rules/<rule_id>/delete */
+
+#[post("/admin/rules/<rule_id>/delete", data = "<_csrf>")]
+fn admin_delete_content_rule(
+    rule_id: i32,
+    repo: web::guard::Repository,
+    admin: web::guard::Admin,
+    _csrf: web::csrf::CsrfForm<web::csrf::NoFields>,
+) -> Result<response::Redirect, status::NotFound<&'static str>> {
+    match repo.delete_content_rule(rule_id) {
+        Some(rule) => {
+            audit::record(
+                &repo,
+                &admin.username,
+                audit::Action::ContentRuleDeleted,
+                &rule.id.to_string(),
+                Some(&ContentRuleDTO::from(&rule)),
+                None::<&ContentRuleDTO>,
+            );
+            Ok(response::Redirect::to("/admin/rules"))
+        }
+        None => Err(status::NotFound("not found")),
+    }
+}
+
+/* GET /admin/tokens */
+
+#[derive(Serialize, Debug)]
+struct ApiTokenDTO {
+    pub id: i32,
+    pub name: String,
+    pub scope: String,
+    pub created_at_recognizable: String,
+    pub last_used_at_recognizable: Option<String>,
+    pub revoked: bool,
+}
+
+impl ApiTokenDTO {
+    fn from(t: &model::ApiToken) -> Self {
+        Self {
+            id: t.id,
+            name: t.name.clone(),
+            scope: String::from(t.scope.as_str()),
+            created_at_recognizable: utils::recognizable_datetime(t.created_at),
+            last_used_at_recognizable: t.last_used_at.map(|d| utils::recognizable_datetime(d)),
+            revoked: t.revoked_at.is_some(),
+        }
+    }
+}
+
+#[derive(Serialize, Debug)]
+struct AdminApiTokensDTO {
+    pub tokens: Vec<ApiTokenDTO>,
+    pub scopes: Vec<String>,
+    // shown only once, right after the token is created
+    pub created_token: Option<String>,
+    pub error: Option<String>,
+    pub csrf_token: String,
+}
+
+#[get("/admin/tokens")]
+fn admin_api_tokens(
+    repo: web::guard::Repository,
+    admin: web::guard::Admin,
+    flash: Option<request::FlashMessage>,
+    csrf: web::csrf::CsrfToken,
+) -> Template {
+    render_admin_api_tokens(&repo, &admin, None, flash.map(|f| String::from(f.msg())), csrf)
+}
+
+fn render_admin_api_tokens(
+    repo: &model::Repository,
+    admin: &model::Admin,
+    created_token: Option<String>,
+    error: Option<String>,
+    csrf: web::csrf::CsrfToken,
+) -> Template {
+    let context = AdminApiTokensDTO {
+        tokens: repo
+            .api_tokens(&admin.username)
+            .iter()
+            .map(|t| ApiTokenDTO::from(t))
+            .collect(),
+        scopes: model::TokenScope::all()
+            .into_iter()
+            .map(|s| String::from(s.as_str()))
+            .collect(),
+        created_token: created_token,
+        error: error,
+        csrf_token: csrf.0,
+    };
+    Template::render("admin/tokens/index", &context)
+}
+
+/* POST /admin/tokens */
+
+#[derive(FromForm)]
+struct NewApiTokenForm {
+    name: String,
+    scope: String,
+}
+
+#[post("/admin/tokens", data = "<params>")]
+fn admin_create_api_token(
+    repo: web::guard::Repository,
+    admin: web::guard::Admin,
+    params: web::csrf::CsrfForm<NewApiTokenForm>,
+    csrf: web::csrf::CsrfToken,
+) -> Result<Template, response::Flash<response::Redirect>> {
+    let error = |message| response::Flash::error(response::Redirect::to("/admin/tokens"), message);
+    let name = params.name.trim();
+    if name.is_empty() {
+        return Err(error("トークンの名前を入力してください"));
+    }
+    let scope = model::TokenScope::parse(&params.scope).ok_or_else(|| error("権限が正しくありません"))?;
+
+    let token = api_token::generate();
+    let stored = repo.store_api_token(
+        admin.username.clone(),
+        String::from(name),
+        api_token::hash(&token),
+        scope,
+    );
+    audit::record(
+        &repo,
+        &admin.username,
+        audit::Action::ApiTokenCreated,
+        &stored.id.to_string(),
+        None::<&ApiTokenDTO>,
+        Some(&ApiTokenDTO::from(&stored)),
+    );
+    Ok(render_admin_api_tokens(&repo, &admin, Some(token), None, csrf))
+}
+
+/* POST /admin/tokens/<token_id>/revoke */
+
+#[post("/admin/tokens/<token_id>/revoke", data = "<_csrf>")]
+fn admin_revoke_api_token(
+    token_id: i32,
+    repo: web::guard::Repository,
+    admin: web::guard::Admin,
+    _csrf: web::csrf::CsrfForm<web::csrf::NoFields>,
+) -> Result<response::Redirect, status::NotFound<&'static str>> {
+    match repo.revoke_api_token(&admin.username, token_id) {
+        Some(token) => {
+            audit::record(
+                &repo,
+                &admin.username,
+                audit::Action::ApiTokenRevoked,
+                &token.id.to_string(),
+                None::<&ApiTokenDTO>,
+                Some(&ApiTokenDTO::from(&token)),
+            );
+            Ok(response::Redirect::to("/admin/tokens"))
+        }
+        None => Err(status::NotFound("not found")),
+    }
+}
 
-    let user_profile = UserProfile {
-        name: env::var("PROFILE_USERNAME").unwrap(),
+/* GET /api/admin/questions */
+
+#[derive(Serialize, Debug)]
+struct ApiQuestionDTO {
+    pub id: i32,
+    pub body: String,
+    pub status: String,
+    // hidden or spam
+    pub hidden: bool,
+    pub received_count: i32,
+    pub created_at: DateTime<Local>,
+}
+
+impl ApiQuestionDTO {
+    fn from(q: model::Question) -> Self {
+        Self {
+            id: q.id,
+            body: q.body,
+            status: String::from(q.status.as_str()),
+            hidden: q.status == model::QuestionStatus::Hidden
+                || q.status == model::QuestionStatus::Spam,
+            received_count: q.received_count,
+            created_at: q.created_at,
+        }
+    }
+}
+
+#[derive(Serialize, Debug)]
+struct ApiQuestionsDTO {
+    pub questions: Vec<ApiQuestionDTO>,
+}
+
+// Not answered questions, including hidden and spam ones.
+#[get("/api/admin/questions")]
+fn api_admin_questions(
+    repo: web::guard::Repository,
+    _client: web::guard::ApiClient,
+) -> Json<ApiQuestionsDTO> {
+    Json(ApiQuestionsDTO {
+        questions: repo
+            .questions_by_status(&model::QuestionStatus::unanswered())
+            .into_iter()
+            .map(|q| ApiQuestionDTO::from(q))
+            .collect(),
+    })
+}
+
+/* POST /api/admin/questions/<question_id>/answer */
+
+#[derive(Deserialize, Debug)]
+struct ApiAnswerForm {
+    body: String,
+    #[serde(default)]
+    tweet: bool,
+}
+
+#[derive(Serialize, Debug)]
+struct ApiPostAnswerDTO {
+    pub answer: AnswerDTO,
+    pub cross_posts: Vec<CrossPostDTO>,
+}
+
+#[post("/api/admin/questions/<question_id>/answer", format = "json", data = "<params>")]
+fn api_admin_post_answer(
+    question_id: i32,
+    repo: web::guard::Repository,
+    pool: State<model::DieselPool>,
+    webhooks: State<webhook::Webhooks>,
+    mastodon: State<Option<mastodon::Client>>,
+    twitter: State<Option<twitter::Client>>,
+    limits: State<model::LengthLimits>,
+    params: Json<ApiAnswerForm>,
+    writer: web::guard::ApiWriter,
+) -> Result<status::Created<Json<ApiPostAnswerDTO>>, status::Custom<&'static str>> {
+    if !writer.0.admin.role.can_answer() {
+        return Err(status::Custom(Status::Forbidden, "forbidden"));
+    }
+    if params.body.trim().is_empty() {
+        return Err(status::Custom(Status::UnprocessableEntity, "body is blank"));
+    }
+    let publisher = AnswerPublisher {
+        repo: &repo,
+        pool: &pool,
+        webhooks: &webhooks,
+        mastodon: mastodon.inner().as_ref(),
+        twitter: twitter.inner().as_ref(),
+        max_length: limits.answer,
+    };
+    match publisher.publish(&writer.0.actor(), question_id, params.body.clone(), params.tweet) {
+        Ok((answer, cross_posts)) => Ok(status::Created(
+            answer_url(&answer),
+            Some(Json(ApiPostAnswerDTO {
+                answer: AnswerDTO::from(answer),
+                cross_posts: cross_posts,
+            })),
+        )),
+        Err(model::StoreAnswerError::QuestionNotFound) => {
+            Err(status::Custom(Status::NotFound, "not found"))
+        }
+        Err(model::StoreAnswerError::TooLong { .. }) => {
+            Err(status::Custom(Status::UnprocessableEntity, "body is too long"))
+        }
+        Err(model::StoreAnswerError::AlreadyAnswered) => {
+            Err(status::Custom(Status::Conflict, "already answered"))
+        }
+    }
+}
+
+/* POST /api/admin/questions/<question_id>/hide, unhide */
+
+#[post("/api/admin/questions/<question_id>/hide")]
+fn api_admin_hide_question(
+    question_id: i32,
+    repo: web::guard::Repository,
+    writer: web::guard::ApiWriter,
+) -> Result<Json<ApiQuestionDTO>, status::Custom<&'static str>> {
+    update_question_status(&repo, &writer.0.actor(), question_id, model::QuestionStatus::Hidden)
+        .map(|q| Json(ApiQuestionDTO::from(q)))
+        .map_err(|err| err.to_status())
+}
+
+#[post("/api/admin/questions/<question_id>/unhide")]
+fn api_admin_unhide_question(
+    question_id: i32,
+    repo: web::guard::Repository,
+    writer: web::guard::ApiWriter,
+) -> Result<Json<ApiQuestionDTO>, status::Custom<&'static str>> {
+    update_question_status(&repo, &writer.0.actor(), question_id, model::QuestionStatus::Inbox)
+        .map(|q| Json(ApiQuestionDTO::from(q)))
+        .map_err(|err| err.to_status())
+}
+
+/* GET /admin/login */
+
+#[derive(Serialize, Debug)]
+struct AdminLoginDTO {
+    pub error: Option<String>,
+    pub csrf_token: String,
+}
+
+#[get("/admin/login")]
+fn admin_login_form(
+    flash: Option<request::FlashMessage>,
+    csrf: web::csrf::CsrfToken,
+) -> Template {
+    let context = AdminLoginDTO {
+        error: flash.map(|f| String::from(f.msg())),
+        csrf_token: csrf.0,
     };
+    Template::render("admin/login", &context)
+}
+
+/* POST /admin/login */
+
+#[derive(FromForm)]
+struct AdminLoginForm {
+    username: String,
+    password: String,
+}
+
+#[post("/admin/login", data = "<params>")]
+fn admin_login(
+    repo: web::guard::Repository,
+    params: web::csrf::CsrfForm<AdminLoginForm>,
+    mut cookies: Cookies,
+) -> Result<response::Redirect, response::Flash<response::Redirect>> {
+    let admin = repo.find_admin(&params.username);
+    let verified = match admin {
+        Some(ref admin) => password::verify(&admin.password_hash, &params.password),
+        None => password::verify_nothing(&params.password),
+    };
+    match admin {
+        Some(ref admin) if verified && admin.totp_enabled => {
+            web::guard::PendingLogin::start(&mut cookies, admin.username.clone());
+            Ok(response::Redirect::to("/admin/login/totp"))
+        }
+        Some(ref admin) if verified => {
+            web::guard::AdminSession::start(&mut cookies, admin.username.clone());
+            Ok(response::Redirect::to("/admin"))
+        }
+        _ => Err(response::Flash::error(
+            response::Redirect::to("/admin/login"),
+            "ユーザー名またはパスワードが違います",
+        )),
+    }
+}
+
+/* GET /admin/login/totp */
+
+#[get("/admin/login/totp")]
+fn admin_login_totp_form(
+    _pending: web::guard::PendingLogin,
+    flash: Option<request::FlashMessage>,
+    csrf: web::csrf::CsrfToken,
+) -> Template {
+    let context = AdminLoginDTO {
+        error: flash.map(|f| String::from(f.msg())),
+        csrf_token: csrf.0,
+    };
+    Template::render("admin/login_totp", &context)
+}
+
+/* POST /admin/login/totp */
+
+#[derive(FromForm)]
+struct TotpForm {
+    code: String,
+}
+
+// Accepts either a TOTP code or an unused recovery code. Each TOTP code is
+// accepted only once.
+fn verify_second_factor(
+    repo: &model::Repository,
+    admin: &model::Admin,
+    code: &str,
+) -> bool {
+    match admin.totp_secret {
+        Some(ref secret) if admin.totp_enabled => {
+            match totp::verified_step(secret, code, Utc::now().timestamp()) {
+                Some(step) => repo.use_totp_step(&admin.username, step),
+                None => repo.use_recovery_code(&admin.username, &totp::hash_recovery_code(code)),
+            }
+        }
+        _ => false,
+    }
+}
+
+// Wrong codes in a row before the second factor is locked. The count is kept
+// per admin, so that logging in with the password again does not reset it.
+const MAX_TOTP_ATTEMPTS: i32 = 5;
+const TOTP_LOCK_MINUTES: i64 = 15;
+
+#[post("/admin/login/totp", data = "<params>")]
+fn admin_login_totp(
+    repo: web::guard::Repository,
+    pending: web::guard::PendingLogin,
+    params: web::csrf::CsrfForm<TotpForm>,
+    mut cookies: Cookies,
+) -> Result<response::Redirect, response::Flash<response::Redirect>> {
+    let locked = || {
+        response::Flash::error(
+            response::Redirect::to("/admin/login"),
+            "確認コードを続けて間違えたため，しばらくログインできません",
+        )
+    };
+    let admin = match repo.find_admin(&pending.username) {
+        Some(admin) => admin,
+        None => {
+            web::guard::PendingLogin::end(&mut cookies);
+            return Err(response::Flash::error(
+                response::Redirect::to("/admin/login"),
+                "ユーザー名またはパスワードが違います",
+            ));
+        }
+    };
+    if admin.totp_locked_until.map_or(false, |until| until > Local::now()) {
+        web::guard::PendingLogin::end(&mut cookies);
+        return Err(locked());
+    }
+
+    if verify_second_factor(&repo, &admin, &params.code) {
+        repo.reset_totp_failures(&admin.username);
+        web::guard::AdminSession::start(&mut cookies, pending.username);
+        return Ok(response::Redirect::to("/admin"));
+    }
+    let locked_until = Local::now() + chrono::Duration::minutes(TOTP_LOCK_MINUTES);
+    if repo.record_totp_failure(&admin.username, MAX_TOTP_ATTEMPTS, locked_until) {
+        web::guard::PendingLogin::end(&mut cookies);
+        Err(locked())
+    } else {
+        Err(response::Flash::error(
+            response::Redirect::to("/admin/login/totp"),
+            "確認コードが違います",
+        ))
+    }
+}
+
+/* POST /admin/logout */
+
+#[post("/admin/logout", data = "<_csrf>")]
+fn admin_logout(
+    _csrf: web::csrf::CsrfForm<web::csrf::NoFields>,
+    mut cookies: Cookies,
+) -> response::Redirect {
+    web::guard::AdminSession::end(&mut cookies);
+    response::Redirect::to("/admin/login")
+}
+
+/* GET /admin/totp */
+
+#[derive(Serialize, Debug)]
+struct AdminTotpDTO {
+    pub enabled: bool,
+    pub secret: Option<String>,
+    pub qr_code_svg: Option<String>,
+    pub error: Option<String>,
+    pub csrf_token: String,
+}
+
+const TOTP_ISSUER: &'static str = "Reing";
+#[get("/admin/totp")]
+fn admin_totp(
+    repo: web::guard::Repository,
+    admin: web::guard::Admin,
+    flash: Option<request::FlashMessage>,
+    csrf: web::csrf::CsrfToken,
+) -> Template {
+    let error = flash.map(|f| String::from(f.msg()));
+    let context = if admin.totp_enabled {
+        AdminTotpDTO {
+            enabled: true,
+            secret: None,
+            qr_code_svg: None,
+            error: error,
+            csrf_token: csrf.0,
+        }
+    } else {
+        // keep showing the same secret until the enrolment is confirmed
+        let secret = admin.totp_secret.clone().unwrap_or_else(|| {
+            let secret = totp::generate_secret();
+            repo.store_totp_secret(&admin.username, secret.clone());
+            secret
+        });
+        let uri = totp::provisioning_uri(&secret, &admin.username, TOTP_ISSUER);
+        AdminTotpDTO {
+            enabled: false,
+            qr_code_svg: Some(totp::qr_code_svg(&uri)),
+            secret: Some(secret),
+            error: error,
+            csrf_token: csrf.0,
+        }
+    };
+    Template::render("admin/totp/index", &context)
+}
+
+/* POST /admin/totp/confirm */
+
+#[derive(Serialize, Debug)]
+struct AdminRecoveryCodesDTO {
+    pub recovery_codes: Vec<String>,
+    pub csrf_token: String,
+}
+
+#[post("/admin/totp/confirm", data = "<params>")]
+fn admin_confirm_totp(
+    repo: web::guard::Repository,
+    admin: web::guard::Admin,
+    params: web::csrf::CsrfForm<TotpForm>,
+    csrf: web::csrf::CsrfToken,
+) -> Result<Template, response::Flash<response::Redirect>> {
+    // the step is recorded as used, so the code can not pass a login later
+    let confirmed = match admin.totp_secret {
+        Some(ref secret) => totp::verified_step(secret, &params.code, Utc::now().timestamp())
+            .map_or(false, |step| repo.use_totp_step(&admin.username, step)),
+        None => false,
+    };
+    if !confirmed {
+        return Err(response::Flash::error(
+            response::Redirect::to("/admin/totp"),
+            "確認コードが違います",
+        ));
+    }
+
+    let recovery_codes = totp::generate_recovery_codes();
+    repo.enable_totp(
+        &admin.username,
+        recovery_codes
+            .iter()
+            .map(|code| totp::hash_recovery_code(code))
+            .collect(),
+    );
+    audit::record(
+        &repo,
+        &admin.username,
+        audit::Action::TotpEnabled,
+        &admin.username,
+        Some(&audit::AdminSnapshot::from(&admin)),
+        repo.find_admin(&admin.username).map(|a| audit::AdminSnapshot::from(&a)).as_ref(),
+    );
+    let context = AdminRecoveryCodesDTO {
+        recovery_codes: recovery_codes,
+        csrf_token: csrf.0,
+    };
+    Ok(Template::render("admin/totp/recovery_codes", &context))
+}
+
+/* POST /admin/totp/disable */
+
+#[post("/admin/totp/disable", data = "<params>")]
+fn admin_disable_totp(
+    repo: web::guard::Repository,
+    admin: web::guard::Admin,
+    params: web::csrf::CsrfForm<TotpForm>,
+) -> Result<response::Redirect, response::Flash<response::Redirect>> {
+    if verify_second_factor(&repo, &admin, &params.code) {
+        repo.disable_totp(&admin.username);
+        audit::record(
+            &repo,
+            &admin.username,
+            audit::Action::TotpDisabled,
+            &admin.username,
+            Some(&audit::AdminSnapshot::from(&admin)),
+            repo.find_admin(&admin.username).map(|a| audit::AdminSnapshot::from(&a)).as_ref(),
+        );
+        Ok(response::Redirect::to("/admin/totp"))
+    } else {
+        Err(response::Flash::error(
+            response::Redirect::to("/admin/totp"),
+            "確認コードが違います",
+        ))
+    }
+}
+
+/* Force login */
+
+#[derive(Serialize, Debug)]
+struct ApiErrorDTO {
+    pub error: &'static str,
+}
+
+// Scripts get a JSON error instead of the login page.
+// Forms too large for LengthLimits::form_limit, far beyond the limits.
+#[catch(413)]
+fn payload_too_large(req: &Request) -> Option<status::Custom<Template>> {
+    let limits = req.guard::<State<model::LengthLimits>>().succeeded()?;
+    let challenger = req.guard::<State<challenge::Challenger>>().succeeded()?;
+    let spam_trap = req.guard::<State<spam::SpamTrap>>().succeeded()?;
+    let csrf = req.guard::<web::csrf::CsrfToken>().succeeded()?;
+    Some(post_question_failed(
+        Status::PayloadTooLarge,
+        "送信された内容が長すぎます",
+        QuestionFormDTO::new(&limits, &challenger, &spam_trap),
+        csrf,
+    ))
+}
+
+#[catch(401)]
+fn unauthorized(req: &Request) -> Result<response::Redirect, Json<ApiErrorDTO>> {
+    if req.uri().path().starts_with("/api/") {
+        Err(Json(ApiErrorDTO {
+            error: "invalid or missing bearer token",
+        }))
+    } else {
+        Ok(response::Redirect::to("/admin/login"))
+    }
+}
+
+#[derive(Clone)]
+struct AppEnvironment {
+    pub is_production: bool,
+}
+
+fn database_url() -> String {
+    let user = env::var("POSTGRES_USER").expect("POSTGRES_USER not defined");
+    let pw = env::var("POSTGRES_PASSWORD").expect("POSTGRES_PASSWORD not defined");
+    let host = env::var("POSTGRES_HOST").expect("POSTGRES_HOST not defined");
+    let db = env::var("POSTGRES_DB").expect("POSTGRES_DB not defined");
+    let database_url = format!(
+        "postgresql://{}:{}@{}:5432/{}", user, pw, host, db
+    );
+    database_url
+}
+
+fn main() {
+    dotenv::dotenv().ok();
+    let manager = r2d2_diesel::ConnectionManager::<diesel::PgConnection>::new(
+        database_url(),
+    );
+    let pool = r2d2::Pool::builder().max_size(15).build(manager).unwrap();
+
+    let args = env::args().skip(1).collect::<Vec<_>>();
+    if !args.is_empty() {
+        cli::run(&args, pool);
+        return;
+    }
+
+    cli::seed_admin_from_env(&model::Repository::new(
+        pool.get().expect("Failed to get connection."),
+    ));
+
+    let user_profile = profile::UserProfile::resolve();
 
     let app_env = AppEnvironment {
         is_production: env::var("MODE")
             .map(|mode| mode == "production")
             .unwrap_or(false),
     };
+    // shared, so that both see the same random secret when it is not configured
+    let signer = signer::Signer::from_env();
 
     rocket::ignite()
         .manage(pool)
         .manage(user_profile)
         .manage(app_env)
+        .manage(notify::Notifier::from_env())
+        .manage(webhook::Webhooks::from_env())
+        .manage(mastodon::Client::from_env())
+        .manage(twitter::Client::from_env())
+        .manage(sender::SenderHasher::from_env())
+        .manage(rate_limit::RateLimiter::from_env())
+        .manage(sender::BlockMode::from_env())
+        .manage(model::LengthLimits::from_env())
+        .manage(challenge::Challenger::from_env(signer.clone()))
+        .manage(spam::SpamTrap::from_env(signer))
+        .manage(classifier::Settings::from_env())
+        .manage(report::AnswerReports::from_env())
         .mount(
             "/",
             routes![
@@ -558,18 +2837,57 @@ fn main() {
                 after_post_question,
                 show_answer,
                 admin_index,
+                admin_login_form,
+                admin_login,
+                admin_logout,
+                admin_login_totp_form,
+                admin_login_totp,
+                admin_totp,
+                admin_confirm_totp,
+                admin_disable_totp,
                 admin_post_answer,
                 admin_show_question,
                 admin_hide_question,
+                admin_update_question_status,
+                admin_bulk_questions,
+                admin_block_sender,
+                admin_unblock_sender,
+                admin_answers,
+                admin_show_answer,
+                admin_update_answer,
+                admin_delete_answer,
+                admin_publish_answer,
+                admin_unpublish_answer,
+                admin_answer_reports,
+                admin_dismiss_answer_reports,
+                admin_cross_post_answer,
+                admin_webhook_deliveries,
+                admin_admins,
+                admin_create_admin,
+                admin_update_admin_role,
+                admin_delete_admin,
+                admin_audit_events,
+                admin_api_tokens,
+                admin_create_api_token,
+                admin_revoke_api_token,
+                admin_content_rules,
+                admin_create_content_rule,
+                admin_delete_content_rule,
+                api_admin_questions,
+                api_admin_post_answer,
+                api_admin_hide_question,
+                api_admin_unhide_question,
                 search,
                 show_question,
                 show_answer_json,
+                report_answer,
                 show_random_answer,
                 show_random_answer_json,
                 show_question_image,
             ],
         )
-        .register(catchers![unauthorized])
+        .register(catchers![unauthorized, payload_too_large])
+        .attach(web::csrf::CsrfFairing)
         .attach(Template::fairing())
         .launch();
 }
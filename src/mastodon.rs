@@ -0,0 +1,108 @@
+use reqwest;
+use std::env;
+use std::path::Path;
+use std::time::Duration;
+
+pub const SERVICE_NAME: &'static str = "mastodon";
+
+#[derive(Deserialize, Debug)]
+struct MediaAttachment {
+    pub id: String,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Status {
+    pub id: String,
+    pub uri: String,
+    pub url: Option<String>,
+}
+
+impl Status {
+    pub fn permalink(&self) -> String {
+        self.url.clone().unwrap_or(self.uri.clone())
+    }
+}
+
+pub struct Client {
+    instance_url: String,
+    access_token: String,
+    http: reqwest::Client,
+}
+
+#[test]
+fn post_status_test() {
+    let _media = mockito::mock("POST", "/api/v1/media")
+        .match_header("authorization", "Bearer token")
+        .with_body(r#"{"id":"10"}"#)
+        .create();
+    let _status = mockito::mock("POST", "/api/v1/statuses")
+        .match_header("authorization", "Bearer token")
+        .match_body(mockito::Matcher::UrlEncoded(
+            String::from("media_ids[]"),
+            String::from("10"),
+        ))
+        .with_body(r#"{"id":"20","uri":"https://example.com/users/reing/statuses/20","url":"https://example.com/@reing/20"}"#)
+        .create();
+
+    let client = Client::new(mockito::server_url(), String::from("token"));
+    let status = client
+        .post_status("回答です", Some((Path::new("static/image/logo.png"), "質問です")))
+        .unwrap();
+    assert_eq!(status.id, "20");
+    assert_eq!(status.permalink(), "https://example.com/@reing/20");
+}
+
+impl Client {
+    pub fn new(instance_url: String, access_token: String) -> Self {
+        Self {
+            instance_url: instance_url.trim_end_matches('/').to_string(),
+            access_token: access_token,
+            http: reqwest::Client::builder()
+                .timeout(Duration::from_secs(30))
+                .build()
+                .expect("Failed to initialize http client."),
+        }
+    }
+
+    // Returns None unless both MASTODON_INSTANCE_URL and MASTODON_ACCESS_TOKEN are set.
+    pub fn from_env() -> Option<Self> {
+        match (env::var("MASTODON_INSTANCE_URL"), env::var("MASTODON_ACCESS_TOKEN")) {
+            (Ok(instance_url), Ok(access_token)) => Some(Self::new(instance_url, access_token)),
+            _ => None,
+        }
+    }
+
+    // image: (path to the image, alt text)
+    pub fn post_status(&self, text: &str, image: Option<(&Path, &str)>) -> Result<Status, String> {
+        let mut params = vec![(String::from("status"), String::from(text))];
+        if let Some((path, description)) = image {
+            let media = self.upload_media(path, description)?;
+            params.push((String::from("media_ids[]"), media.id));
+        }
+
+        self.http
+            .post(&format!("{}/api/v1/statuses", self.instance_url))
+            .bearer_auth(&self.access_token)
+            .form(&params)
+            .send()
+            .and_then(|response| response.error_for_status())
+            .and_then(|mut response| response.json::<Status>())
+            .map_err(|err| format!("{}", err))
+    }
+
+    fn upload_media(&self, path: &Path, description: &str) -> Result<MediaAttachment, String> {
+        let form = reqwest::multipart::Form::new()
+            .text("description", String::from(description))
+            .file("file", path)
+            .map_err(|err| format!("{}", err))?;
+
+        self.http
+            .post(&format!("{}/api/v1/media", self.instance_url))
+            .bearer_auth(&self.access_token)
+            .multipart(form)
+            .send()
+            .and_then(|response| response.error_for_status())
+            .and_then(|mut response| response.json::<MediaAttachment>())
+            .map_err(|err| format!("{}", err))
+    }
+}
@@ -1,15 +1,24 @@
 use chrono::prelude::*;
+use classifier;
 use db;
-use db::schema::{answers, questions};
+use db::schema::{
+    admin_recovery_codes, admins, answer_reports, answers, api_tokens, audit_events, blocked_senders, classifier_documents, classifier_tokens, content_rules, cross_posts, questions, spam_counters, webhook_deliveries,
+};
 use diesel;
 use diesel::ExpressionMethods;
+use diesel::OptionalExtension;
 use diesel::QueryDsl;
 use diesel::RunQueryDsl;
 use diesel::SaveChangesDsl;
 use r2d2;
 use r2d2_diesel;
+use regex::{Regex, RegexBuilder};
+use std::env;
 use std::ops::Deref;
+use unicode_segmentation::UnicodeSegmentation;
+use utils;
 
+pub type DieselPool = r2d2::Pool<r2d2_diesel::ConnectionManager<diesel::PgConnection>>;
 type DieselConnection =
     r2d2::PooledConnection<r2d2_diesel::ConnectionManager<diesel::PgConnection>>;
 
@@ -18,7 +27,161 @@ pub struct Question {
     pub id: i32,
     pub body: String,
     pub created_at: DateTime<Local>,
-    pub hidden: bool,
+    pub status: QuestionStatus,
+    // keyed hash of the sender's IP address, which rotates periodically
+    pub sender_hash: Option<String>,
+    // how many times the same (or nearly the same) question has been sent
+    pub received_count: i32,
+    // by the classifier when the question arrived, None until it has been trained
+    pub spam_score: Option<f64>,
+}
+
+// Where a question is in moderation.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum QuestionStatus {
+    // waiting for a review before it goes to the inbox
+    Pending,
+    Inbox,
+    Hidden,
+    Spam,
+    Answered,
+}
+
+impl QuestionStatus {
+    pub fn all() -> Vec<QuestionStatus> {
+        vec![
+            QuestionStatus::Pending,
+            QuestionStatus::Inbox,
+            QuestionStatus::Hidden,
+            QuestionStatus::Spam,
+            QuestionStatus::Answered,
+        ]
+    }
+
+    pub fn parse(s: &str) -> Option<QuestionStatus> {
+        match s {
+            "pending" => Some(QuestionStatus::Pending),
+            "inbox" => Some(QuestionStatus::Inbox),
+            "hidden" => Some(QuestionStatus::Hidden),
+            "spam" => Some(QuestionStatus::Spam),
+            "answered" => Some(QuestionStatus::Answered),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            QuestionStatus::Pending => "pending",
+            QuestionStatus::Inbox => "inbox",
+            QuestionStatus::Hidden => "hidden",
+            QuestionStatus::Spam => "spam",
+            QuestionStatus::Answered => "answered",
+        }
+    }
+
+    // Statuses of questions which have not been answered yet.
+    pub fn unanswered() -> Vec<QuestionStatus> {
+        vec![
+            QuestionStatus::Pending,
+            QuestionStatus::Inbox,
+            QuestionStatus::Hidden,
+            QuestionStatus::Spam,
+        ]
+    }
+
+    // Visible to the admin without looking into hidden or spam questions.
+    pub fn is_visible(&self) -> bool {
+        *self == QuestionStatus::Pending || *self == QuestionStatus::Inbox
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum QuestionOrder {
+    Newest,
+    Oldest,
+}
+
+impl QuestionOrder {
+    pub fn all() -> Vec<QuestionOrder> {
+        vec![QuestionOrder::Newest, QuestionOrder::Oldest]
+    }
+
+    pub fn parse(s: &str) -> Option<QuestionOrder> {
+        match s {
+            "newest" => Some(QuestionOrder::Newest),
+            "oldest" => Some(QuestionOrder::Oldest),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            QuestionOrder::Newest => "newest",
+            QuestionOrder::Oldest => "oldest",
+        }
+    }
+}
+
+// Position after the last question of a page, as `<microseconds>_<id>`.
+// The id breaks ties between questions created at the same time.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct QuestionCursor {
+    pub created_at: DateTime<Utc>,
+    pub id: i32,
+}
+
+#[test]
+fn question_cursor_test() {
+    let cursor = QuestionCursor {
+        created_at: Utc.timestamp(1_500_000_000, 123_456_000),
+        id: 42,
+    };
+    assert_eq!("1500000000123456_42", cursor.encode());
+    assert_eq!(Some(cursor), QuestionCursor::parse(&cursor.encode()));
+    assert_eq!(None, QuestionCursor::parse("1500000000123456"));
+    assert_eq!(None, QuestionCursor::parse("abc_42"));
+    // out of the range of dates
+    assert_eq!(None, QuestionCursor::parse(&format!("{}_42", i64::MAX)));
+}
+
+impl QuestionCursor {
+    fn of(question: &Question) -> Self {
+        Self {
+            created_at: question.created_at.with_timezone(&Utc),
+            id: question.id,
+        }
+    }
+
+    pub fn encode(&self) -> String {
+        let micros = self.created_at.timestamp() * 1_000_000
+            + self.created_at.timestamp_subsec_micros() as i64;
+        format!("{}_{}", micros, self.id)
+    }
+
+    pub fn parse(s: &str) -> Option<QuestionCursor> {
+        let mut parts = s.splitn(2, '_');
+        let micros = parts.next()?.parse::<i64>().ok()?;
+        let id = parts.next()?.parse::<i32>().ok()?;
+        if micros < 0 {
+            return None;
+        }
+        let created_at = Utc
+            .timestamp_opt(micros / 1_000_000, (micros % 1_000_000) as u32 * 1000)
+            .single()?;
+        Some(QuestionCursor {
+            created_at: created_at,
+            id: id,
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct QuestionPage {
+    pub questions: Vec<Question>,
+    // of all pages
+    pub total: i64,
+    // None on the last page
+    pub next_cursor: Option<QuestionCursor>,
 }
 
 #[derive(Debug, Clone)]
@@ -26,16 +189,330 @@ pub struct Answer {
     pub id: i32,
     pub body: String,
     pub created_at: DateTime<Local>,
+    pub published: bool,
     pub question: Question,
 }
 
+#[derive(Debug, Clone)]
+pub struct AnswerReport {
+    pub id: i32,
+    pub reason: String,
+    pub created_at: DateTime<Local>,
+    pub answer: Answer,
+}
+
+#[derive(Debug, Clone)]
+pub struct WebhookDelivery {
+    pub id: i32,
+    pub event: String,
+    pub url: String,
+    pub payload: String,
+    pub status_code: Option<i32>,
+    pub error: Option<String>,
+    pub delivered_at: DateTime<Local>,
+}
+
+#[derive(Debug, Clone)]
+pub struct CrossPost {
+    pub service: String,
+    pub remote_id: Option<String>,
+    pub remote_url: Option<String>,
+    pub error: Option<String>,
+    pub updated_at: DateTime<Local>,
+}
+
+#[derive(Debug, Clone)]
+pub struct AuditEvent {
+    pub id: i32,
+    pub actor: String,
+    pub action: String,
+    pub target_id: String,
+    pub before_value: Option<String>,
+    pub after_value: Option<String>,
+    pub created_at: DateTime<Local>,
+}
+
+// Conditions for Repository::audit_events. `to` is exclusive.
+#[derive(Debug, Default)]
+pub struct AuditEventFilter {
+    pub action: Option<String>,
+    pub from: Option<DateTime<Local>>,
+    pub to: Option<DateTime<Local>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Role {
+    // manages other admins and the settings
+    Owner,
+    // publishes answers
+    Answerer,
+    // hides questions
+    Moderator,
+}
+
+impl Role {
+    pub fn all() -> Vec<Role> {
+        vec![Role::Owner, Role::Answerer, Role::Moderator]
+    }
+
+    pub fn parse(s: &str) -> Option<Role> {
+        match s {
+            "owner" => Some(Role::Owner),
+            "answerer" => Some(Role::Answerer),
+            "moderator" => Some(Role::Moderator),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Role::Owner => "owner",
+            Role::Answerer => "answerer",
+            Role::Moderator => "moderator",
+        }
+    }
+
+    pub fn can_answer(&self) -> bool {
+        *self == Role::Owner || *self == Role::Answerer
+    }
+
+    pub fn can_manage(&self) -> bool {
+        *self == Role::Owner
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct BlockedSender {
+    pub sender_hash: String,
+    pub blocked_by: String,
+    pub created_at: DateTime<Local>,
+    // None: no expiry
+    pub expires_at: Option<DateTime<Local>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TokenScope {
+    Read,
+    // implies Read
+    Write,
+}
+
+impl TokenScope {
+    pub fn all() -> Vec<TokenScope> {
+        vec![TokenScope::Read, TokenScope::Write]
+    }
+
+    pub fn parse(s: &str) -> Option<TokenScope> {
+        match s {
+            "read" => Some(TokenScope::Read),
+            "write" => Some(TokenScope::Write),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TokenScope::Read => "read",
+            TokenScope::Write => "write",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RuleAction {
+    Reject,
+    Hide,
+    Flag,
+}
+
+impl RuleAction {
+    pub fn all() -> Vec<RuleAction> {
+        vec![RuleAction::Reject, RuleAction::Hide, RuleAction::Flag]
+    }
+
+    pub fn parse(s: &str) -> Option<RuleAction> {
+        match s {
+            "reject" => Some(RuleAction::Reject),
+            "hide" => Some(RuleAction::Hide),
+            "flag" => Some(RuleAction::Flag),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RuleAction::Reject => "reject",
+            RuleAction::Hide => "hide",
+            RuleAction::Flag => "flag",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ContentRule {
+    pub id: i32,
+    // a plain word unless is_regex
+    pub pattern: String,
+    pub is_regex: bool,
+    pub action: RuleAction,
+    pub created_by: String,
+    pub created_at: DateTime<Local>,
+}
+
+impl ContentRule {
+    // `normalized_body` must be normalized by utils::normalize_for_matching.
+    // Patterns are normalized in the same way, so "ばか" also catches "ﾊﾞｶ".
+    // Invalid regexes never match; they are rejected when the rule is created.
+    pub fn matches(&self, normalized_body: &str) -> bool {
+        if self.is_regex {
+            rule_regex(&self.pattern)
+                .map(|re| re.is_match(normalized_body))
+                .unwrap_or(false)
+        } else {
+            let pattern = utils::normalize_for_matching(&self.pattern);
+            !pattern.is_empty() && normalized_body.contains(&pattern)
+        }
+    }
+}
+
+// Regex patterns keep their case, since lowercasing would change escapes
+// like `\D`; the regex ignores case instead.
+pub fn rule_regex(pattern: &str) -> Option<Regex> {
+    RegexBuilder::new(&utils::fold_width_and_kana(pattern))
+        .case_insensitive(true)
+        .build()
+        .ok()
+}
+
+#[test]
+fn content_rule_matches_test() {
+    let rule = |pattern: &str, is_regex: bool| ContentRule {
+        id: 1,
+        pattern: String::from(pattern),
+        is_regex: is_regex,
+        action: RuleAction::Reject,
+        created_by: String::from("admin"),
+        created_at: Local::now(),
+    };
+    let body = utils::normalize_for_matching("お前はﾊﾞｶだ　ＳＰＡＭ");
+    assert!(rule("ばか", false).matches(&body));
+    assert!(rule("spam", false).matches(&body));
+    assert!(!rule("あほ", false).matches(&body));
+    assert!(rule("^お前は.+だ", true).matches(&body));
+    assert!(!rule("[", true).matches(&body));
+    assert!(rule("ＳＰＡＭ$", true).matches(&body));
+    let digits = utils::normalize_for_matching("12345");
+    assert!(!rule(r"\D+", true).matches(&digits));
+    assert!(rule(r"^\d+$", true).matches(&digits));
+}
+
+#[derive(Debug, Clone)]
+pub struct ApiToken {
+    pub id: i32,
+    pub username: String,
+    pub name: String,
+    pub scope: TokenScope,
+    pub created_at: DateTime<Local>,
+    pub last_used_at: Option<DateTime<Local>>,
+    pub revoked_at: Option<DateTime<Local>>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Admin {
+    pub username: String,
+    pub password_hash: String,
+    pub totp_secret: Option<String>,
+    pub totp_enabled: bool,
+    // set after too many wrong second factors in a row
+    pub totp_locked_until: Option<DateTime<Local>>,
+    pub role: Role,
+    pub created_at: DateTime<Local>,
+}
+
 pub struct Repository {
     pooled_connection: DieselConnection,
 }
 
+// Maximum lengths of question and answer bodies, in grapheme clusters.
+#[derive(Debug, Clone, Copy)]
+pub struct LengthLimits {
+    pub question: usize,
+    pub answer: usize,
+}
+
+impl LengthLimits {
+    pub fn from_env() -> Self {
+        let limit = |key, default| {
+            env::var(key)
+                .ok()
+                .and_then(|n| n.parse().ok())
+                .unwrap_or(default)
+        };
+        Self {
+            question: limit("QUESTION_MAX_LENGTH", 1000),
+            answer: limit("ANSWER_MAX_LENGTH", 2000),
+        }
+    }
+
+    // Maximum size of a URL-encoded form, large enough for any body within
+    // the limits: a grapheme cluster is rarely more than 4 code points of up
+    // to 4 bytes, each byte encoded as "%XX". The rest is for other fields.
+    pub fn form_limit(&self) -> u64 {
+        (self.question.max(self.answer) * 4 * 4 * 3 + 16 * 1024) as u64
+    }
+}
+
+fn grapheme_count(body: &str) -> usize {
+    body.graphemes(true).count()
+}
+
+#[test]
+fn grapheme_count_test() {
+    assert_eq!(grapheme_count("abc"), 3);
+    assert_eq!(grapheme_count("がぎぐ"), 3);
+    // "が" with a combining mark, and a family emoji joined by ZWJ
+    assert_eq!(grapheme_count("\u{304B}\u{3099}"), 1);
+    assert_eq!(grapheme_count("\u{1F468}\u{200D}\u{1F469}\u{200D}\u{1F467}"), 1);
+}
+
 #[derive(Debug)]
 pub enum StoreQuestionError {
     BlankBody,
+    TooLong { max_length: usize },
+    // matched a content rule whose action is reject
+    Rejected(ContentRule),
+    // collapsed into an existing question, whose received_count is incremented
+    Duplicate(Question),
+}
+
+// Exact duplicates are detected among all questions sent within this window.
+const EXACT_DUPLICATE_WINDOW_MINUTES: i64 = 10;
+// Near-duplicates are detected among unanswered questions sent within this window.
+const NEAR_DUPLICATE_WINDOW_HOURS: i64 = 24;
+const NEAR_DUPLICATE_SIMILARITY: f64 = 0.9;
+
+#[derive(Debug)]
+pub enum StoreAnswerError {
+    QuestionNotFound,
+    TooLong { max_length: usize },
+    // questions have at most one answer
+    AlreadyAnswered,
+}
+
+pub const REPORT_REASON_MAX_LENGTH: usize = 500;
+
+#[derive(Debug)]
+pub enum ReportAnswerError {
+    AnswerNotFound,
+    BlankReason,
+    TooLong { max_length: usize },
+    // the same reporter already reported the answer
+    AlreadyReported,
+}
+
+#[derive(Debug)]
+pub enum AdminError {
+    LastOwner,
 }
 
 #[test]
@@ -62,6 +539,31 @@ fn pick_random_answer_test() {
     }
 }
 
+#[test]
+fn move_sender_block_test() {
+    use diesel::Connection;
+
+    dotenv::dotenv().ok();
+    let manager = r2d2_diesel::ConnectionManager::<diesel::PgConnection>::new(
+        std::env::var("DATABASE_URL").unwrap(),
+    );
+    let pool = r2d2::Pool::builder().max_size(1).build(manager).unwrap();
+    let conn = pool.get().unwrap();
+    conn.begin_test_transaction().unwrap();
+    let repo = Repository::new(conn);
+
+    let old_hash = String::from("move-sender-block-test-old");
+    let new_hash = String::from("move-sender-block-test-new");
+    repo.block_sender(old_hash.clone(), String::from("admin"), None);
+    repo.move_sender_block(&old_hash, &new_hash);
+    assert!(repo.active_sender_block(&[old_hash.clone()]).is_none());
+    assert!(repo.active_sender_block(&[new_hash.clone()]).is_some());
+
+    // unblocking the new hash lifts the block, the old one is not copied again
+    repo.unblock_sender(&new_hash);
+    assert!(repo.active_sender_block(&[old_hash, new_hash]).is_none());
+}
+
 impl Repository {
     pub fn new(pooled_connection: DieselConnection) -> Self {
         Self {
@@ -73,43 +575,144 @@ impl Repository {
         self.pooled_connection.deref()
     }
 
+    // Content rules may reject the question, or store it hidden or pending.
+    // With `merge_duplicates`, duplicates of recent questions are not stored
+    // again but counted as received once more.
     pub fn store_question(
         &self,
         body: String,
+        sender_hash: Option<String>,
+        max_length: usize,
+        merge_duplicates: bool,
     ) -> Result<Question, StoreQuestionError> {
         if body.chars().all(|c| char::is_whitespace(c)) {
-            Err(StoreQuestionError::BlankBody)
+            return Err(StoreQuestionError::BlankBody);
+        }
+        if grapheme_count(&body) > max_length {
+            return Err(StoreQuestionError::TooLong {
+                max_length: max_length,
+            });
+        }
+
+        let normalized_body = utils::normalize_for_matching(&body);
+        let matched_rules = self
+            .content_rules()
+            .into_iter()
+            .filter(|rule| rule.matches(&normalized_body))
+            .collect::<Vec<_>>();
+        if let Some(rule) = matched_rules
+            .iter()
+            .find(|rule| rule.action == RuleAction::Reject)
+        {
+            return Err(StoreQuestionError::Rejected(rule.clone()));
+        }
+        if merge_duplicates {
+            if let Some(duplicate) = self.find_duplicate_question(&body) {
+                return Err(StoreQuestionError::Duplicate(
+                    self.increment_received_count(duplicate.id),
+                ));
+            }
+        }
+
+        let status = if matched_rules.iter().any(|rule| rule.action == RuleAction::Hide) {
+            QuestionStatus::Hidden
+        } else if matched_rules.iter().any(|rule| rule.action == RuleAction::Flag) {
+            QuestionStatus::Pending
         } else {
-            let new_question = db::NewQuestion {
-                body: body,
-            };
-
-            let q: db::Question = diesel::insert_into(questions::table)
-                .values(&new_question)
-                .get_result(self.conn())
-                .expect("Error saving new question");
-            Ok(self.db2model_question(q))
-        }
-    }
-
-    pub fn store_answer(&self, question_id: i32, body: String) -> Option<Answer> {
-        self.find_question(question_id).map(|question| {
-            // question_idのquestionが存在することを確認
-            let new_answer = db::NewAnswer {
-                question_id: question.id,
-                body: body,
-            };
-            let a: db::Answer = diesel::insert_into(answers::table)
-                .values(&new_answer)
-                .get_result(self.conn())
-                .expect("Error saving new answer");
-            self.db2model_answer(a, question)
-        })
+            QuestionStatus::Inbox
+        };
+        let spam_score = self.spam_score(&body);
+        let new_question = db::NewQuestion {
+            body: body,
+            sender_hash: sender_hash,
+            spam_score: spam_score,
+            status: String::from(status.as_str()),
+        };
+
+        let q: db::Question = diesel::insert_into(questions::table)
+            .values(&new_question)
+            .get_result(self.conn())
+            .expect("Error saving new question");
+        Ok(self.db2model_question(q))
     }
 
+    fn find_duplicate_question(&self, body: &str) -> Option<Question> {
+        let exact = questions::table
+            .filter(questions::body.eq(body))
+            .filter(
+                questions::created_at
+                    .gt(Utc::now() - chrono::Duration::minutes(EXACT_DUPLICATE_WINDOW_MINUTES)),
+            )
+            .order(questions::created_at.desc())
+            .first::<db::Question>(self.conn())
+            .optional()
+            .unwrap();
+        if let Some(q) = exact {
+            return Some(self.db2model_question(q));
+        }
+
+        // hidden and spam questions are not worth merging new ones into
+        let waiting = [QuestionStatus::Pending, QuestionStatus::Inbox]
+            .iter()
+            .map(|s| s.as_str())
+            .collect::<Vec<_>>();
+        questions::table
+            .filter(questions::status.eq_any(waiting))
+            .filter(
+                questions::created_at
+                    .gt(Utc::now() - chrono::Duration::hours(NEAR_DUPLICATE_WINDOW_HOURS)),
+            )
+            .order(questions::created_at.desc())
+            .load::<db::Question>(self.conn())
+            .unwrap()
+            .into_iter()
+            .find(|q| utils::text_similarity(&q.body, body) >= NEAR_DUPLICATE_SIMILARITY)
+            .map(|q| self.db2model_question(q))
+    }
+
+    fn increment_received_count(&self, question_id: i32) -> Question {
+        let q = diesel::update(questions::table.filter(questions::id.eq(question_id)))
+            .set(questions::received_count.eq(questions::received_count + 1))
+            .get_result::<db::Question>(self.conn())
+            .expect("Error updating received count");
+        self.db2model_question(q)
+    }
+
+    pub fn store_answer(
+        &self,
+        question_id: i32,
+        body: String,
+        max_length: usize,
+    ) -> Result<Answer, StoreAnswerError> {
+        if grapheme_count(&body) > max_length {
+            return Err(StoreAnswerError::TooLong {
+                max_length: max_length,
+            });
+        }
+        let question = self
+            .find_question(question_id)
+            .ok_or(StoreAnswerError::QuestionNotFound)?;
+        let new_answer = db::NewAnswer {
+            question_id: question.id,
+            body: body,
+        };
+        let a: db::Answer = diesel::insert_into(answers::table)
+            .values(&new_answer)
+            .on_conflict(answers::question_id)
+            .do_nothing()
+            .get_result(self.conn())
+            .optional()
+            .expect("Error saving new answer")
+            .ok_or(StoreAnswerError::AlreadyAnswered)?;
+        let question = self.set_question_status(question.id, QuestionStatus::Answered);
+        Ok(self.db2model_answer(a, question))
+    }
+
+    // Published answers only.
     pub fn answers(&self, offset: i64, count: i64) -> Vec<Answer> {
         let answers = answers::table
             .inner_join(questions::table)
+            .filter(answers::published.eq(true))
             .order(answers::created_at.desc())
             .offset(offset)
             .limit(count)
@@ -121,18 +724,101 @@ impl Repository {
             .collect()
     }
 
-    pub fn not_answered_questions(&self) -> Vec<Question> {
-        let qs = questions::table
-            .left_join(answers::table)
-            .filter(answers::id.is_null())
+    // Including unpublished answers.
+    pub fn all_answers(&self, offset: i64, count: i64) -> Vec<Answer> {
+        let answers = answers::table
+            .inner_join(questions::table)
             .order(answers::created_at.desc())
-            .load::<(db::Question, Option<db::Answer>)>(self.conn())
+            .offset(offset)
+            .limit(count)
+            .load::<(db::Answer, db::Question)>(self.conn())
             .unwrap();
-        qs.into_iter()
-            .map(|(q, _)| self.db2model_question(q))
+        answers
+            .into_iter()
+            .map(|(a, q)| self.db2model_answer(a, self.db2model_question(q)))
             .collect()
     }
 
+    // Newest first.
+    pub fn questions_by_status(&self, statuses: &[QuestionStatus]) -> Vec<Question> {
+        let statuses = statuses.iter().map(|s| s.as_str()).collect::<Vec<_>>();
+        questions::table
+            .filter(questions::status.eq_any(statuses))
+            .order(questions::created_at.desc())
+            .load::<db::Question>(self.conn())
+            .unwrap()
+            .into_iter()
+            .map(|q| self.db2model_question(q))
+            .collect()
+    }
+
+    // Keyset paging on (created_at, id), so that questions arriving while
+    // paging do not shift the following pages.
+    pub fn questions_page(
+        &self,
+        statuses: &[QuestionStatus],
+        order: QuestionOrder,
+        after: Option<QuestionCursor>,
+        count: i64,
+    ) -> QuestionPage {
+        use diesel::BoolExpressionMethods;
+
+        let statuses = statuses.iter().map(|s| s.as_str()).collect::<Vec<_>>();
+        let total = questions::table
+            .filter(questions::status.eq_any(statuses.clone()))
+            .count()
+            .get_result::<i64>(self.conn())
+            .unwrap();
+
+        let mut query = questions::table
+            .filter(questions::status.eq_any(statuses))
+            .into_boxed();
+        query = match (order, after) {
+            (QuestionOrder::Newest, Some(c)) => query.filter(
+                questions::created_at
+                    .lt(c.created_at)
+                    .or(questions::created_at.eq(c.created_at).and(questions::id.lt(c.id))),
+            ),
+            (QuestionOrder::Oldest, Some(c)) => query.filter(
+                questions::created_at
+                    .gt(c.created_at)
+                    .or(questions::created_at.eq(c.created_at).and(questions::id.gt(c.id))),
+            ),
+            (_, None) => query,
+        };
+        query = match order {
+            QuestionOrder::Newest => query.order((questions::created_at.desc(), questions::id.desc())),
+            QuestionOrder::Oldest => query.order((questions::created_at.asc(), questions::id.asc())),
+        };
+        // one more to tell whether there is a next page
+        let mut questions = query
+            .limit(count + 1)
+            .load::<db::Question>(self.conn())
+            .unwrap()
+            .into_iter()
+            .map(|q| self.db2model_question(q))
+            .collect::<Vec<_>>();
+        let next_cursor = if questions.len() as i64 > count {
+            questions.truncate(count as usize);
+            questions.last().map(|q| QuestionCursor::of(q))
+        } else {
+            None
+        };
+        QuestionPage {
+            questions: questions,
+            total: total,
+            next_cursor: next_cursor,
+        }
+    }
+
+    pub fn set_question_status(&self, question_id: i32, status: QuestionStatus) -> Question {
+        let q = diesel::update(questions::table.filter(questions::id.eq(question_id)))
+            .set(questions::status.eq(status.as_str()))
+            .get_result::<db::Question>(self.conn())
+            .expect("Error updating question status");
+        self.db2model_question(q)
+    }
+
     pub fn search_answers(&self, keywords_string: String) -> Vec<Answer> {
         use diesel::BoolExpressionMethods;
         use diesel::TextExpressionMethods;
@@ -150,6 +836,7 @@ impl Repository {
 
         let answers = answers::table
             .inner_join(questions::table)
+            .filter(answers::published.eq(true))
             .filter(query_of_keywords)
             .order(answers::created_at.desc())
             .load::<(db::Answer, db::Question)>(self.conn())
@@ -185,6 +872,7 @@ impl Repository {
         let random_id_lower_limit = rng.gen_range(0, max_answer_id);
         let answer = answers::table
             .inner_join(questions::table)
+            .filter(answers::published.eq(true))
             .filter(answers::id.ge(random_id_lower_limit))
             .order(answers::id)
             .limit(1)
@@ -222,6 +910,7 @@ impl Repository {
     pub fn find_next_answer(&self, after: DateTime<Local>) -> Option<Answer> {
         let answer = answers::table
             .inner_join(questions::table)
+            .filter(answers::published.eq(true))
             .filter(answers::created_at.gt(after))
             .order(answers::created_at.asc())
             .limit(1)
@@ -235,6 +924,7 @@ impl Repository {
     pub fn find_prev_answer(&self, before: DateTime<Local>) -> Option<Answer> {
         let answer = answers::table
             .inner_join(questions::table)
+            .filter(answers::published.eq(true))
             .filter(answers::created_at.lt(before))
             .order(answers::created_at.desc())
             .limit(1)
@@ -245,14 +935,925 @@ impl Repository {
         answer.map(|(a, q)| self.db2model_answer(a, self.db2model_question(q)))
     }
 
-    pub fn update_question(&self, question: Question) {
-        let q = db::QuestionForm {
-            id: question.id,
-            body: question.body,
-            hidden: question.hidden,
-            created_at: question.created_at.with_timezone(&Utc),
+    // Other questions from the same sender within the given hash period.
+    pub fn questions_by_sender(
+        &self,
+        sender_hash: &str,
+        except_id: i32,
+        period: (DateTime<Utc>, DateTime<Utc>),
+    ) -> Vec<Question> {
+        questions::table
+            .filter(questions::sender_hash.eq(sender_hash))
+            .filter(questions::id.ne(except_id))
+            .filter(questions::created_at.ge(period.0))
+            .filter(questions::created_at.lt(period.1))
+            .order(questions::created_at.desc())
+            .load::<db::Question>(self.conn())
+            .unwrap()
+            .into_iter()
+            .map(|q| self.db2model_question(q))
+            .collect()
+    }
+
+    // Finds an unexpired block for any of the hashes.
+    pub fn active_sender_block(&self, sender_hashes: &[String]) -> Option<BlockedSender> {
+        use diesel::BoolExpressionMethods;
+
+        blocked_senders::table
+            .filter(blocked_senders::sender_hash.eq_any(sender_hashes))
+            .filter(
+                blocked_senders::expires_at
+                    .is_null()
+                    .or(blocked_senders::expires_at.gt(Utc::now())),
+            )
+            .first::<db::BlockedSender>(self.conn())
+            .optional()
+            .unwrap()
+            .map(|b| BlockedSender {
+                sender_hash: b.sender_hash,
+                blocked_by: b.blocked_by,
+                created_at: b.created_at.with_timezone(&Local),
+                expires_at: b.expires_at.map(|d| d.with_timezone(&Local)),
+            })
+    }
+
+    // Blocking an already blocked sender updates the expiry.
+    pub fn block_sender(
+        &self,
+        sender_hash: String,
+        blocked_by: String,
+        expires_at: Option<DateTime<Local>>,
+    ) {
+        let new_blocked_sender = db::NewBlockedSender {
+            sender_hash: sender_hash,
+            blocked_by: blocked_by,
+            expires_at: expires_at.map(|d| d.with_timezone(&Utc)),
         };
-        q.save_changes::<db::Question>(self.conn()).unwrap();
+        diesel::insert_into(blocked_senders::table)
+            .values(&new_blocked_sender)
+            .on_conflict(blocked_senders::sender_hash)
+            .do_update()
+            .set(&new_blocked_sender)
+            .execute(self.conn())
+            .expect("Error saving blocked sender");
+    }
+
+    // Moves a block to the sender's hash in the new period, so that only
+    // one row has to be removed to unblock them.
+    pub fn move_sender_block(&self, from_hash: &str, to_hash: &str) {
+        use diesel::Connection;
+
+        self.conn()
+            .transaction::<_, diesel::result::Error, _>(|| {
+                diesel::delete(
+                    blocked_senders::table.filter(blocked_senders::sender_hash.eq(to_hash)),
+                )
+                .execute(self.conn())?;
+                diesel::update(
+                    blocked_senders::table.filter(blocked_senders::sender_hash.eq(from_hash)),
+                )
+                .set(blocked_senders::sender_hash.eq(to_hash))
+                .execute(self.conn())?;
+                Ok(())
+            })
+            .expect("Error moving blocked sender");
+    }
+
+    // Bulk actions below apply to unanswered questions among the ids, in one
+    // transaction each, and return the ids of the affected questions.
+
+    pub fn bulk_set_question_status(
+        &self,
+        question_ids: &[i32],
+        status: QuestionStatus,
+    ) -> Vec<i32> {
+        use diesel::Connection;
+
+        self.conn()
+            .transaction::<_, diesel::result::Error, _>(|| {
+                let updated = diesel::update(
+                    questions::table
+                        .filter(questions::id.eq_any(question_ids))
+                        .filter(questions::status.ne(QuestionStatus::Answered.as_str())),
+                )
+                .set(questions::status.eq(status.as_str()))
+                .returning(questions::id)
+                .get_results::<i32>(self.conn())?;
+                if status == QuestionStatus::Hidden || status == QuestionStatus::Spam {
+                    for question_id in &updated {
+                        self.train(*question_id, classifier::Label::Spam)?;
+                    }
+                }
+                Ok(updated)
+            })
+            .expect("Error updating question statuses")
+    }
+
+    pub fn bulk_delete_questions(&self, question_ids: &[i32]) -> Vec<i32> {
+        use diesel::Connection;
+
+        self.conn()
+            .transaction::<_, diesel::result::Error, _>(|| {
+                diesel::delete(
+                    questions::table
+                        .filter(questions::id.eq_any(question_ids))
+                        .filter(questions::status.ne(QuestionStatus::Answered.as_str())),
+                )
+                .returning(questions::id)
+                .get_results::<i32>(self.conn())
+            })
+            .expect("Error deleting questions")
+    }
+
+    // Blocks the senders of the questions. Returns the blocked sender hashes.
+    pub fn bulk_block_senders(
+        &self,
+        question_ids: &[i32],
+        blocked_by: &str,
+        expires_at: Option<DateTime<Local>>,
+    ) -> Vec<String> {
+        use diesel::Connection;
+
+        self.conn()
+            .transaction::<_, diesel::result::Error, _>(|| {
+                let mut sender_hashes = questions::table
+                    .filter(questions::id.eq_any(question_ids))
+                    .filter(questions::status.ne(QuestionStatus::Answered.as_str()))
+                    .filter(questions::sender_hash.is_not_null())
+                    .select(questions::sender_hash)
+                    .load::<Option<String>>(self.conn())?
+                    .into_iter()
+                    .filter_map(|h| h)
+                    .collect::<Vec<_>>();
+                sender_hashes.sort();
+                sender_hashes.dedup();
+                for sender_hash in &sender_hashes {
+                    let new_blocked_sender = db::NewBlockedSender {
+                        sender_hash: sender_hash.clone(),
+                        blocked_by: String::from(blocked_by),
+                        expires_at: expires_at.map(|d| d.with_timezone(&Utc)),
+                    };
+                    diesel::insert_into(blocked_senders::table)
+                        .values(&new_blocked_sender)
+                        .on_conflict(blocked_senders::sender_hash)
+                        .do_update()
+                        .set(&new_blocked_sender)
+                        .execute(self.conn())?;
+                }
+                Ok(sender_hashes)
+            })
+            .expect("Error blocking senders")
+    }
+
+    pub fn unblock_sender(&self, sender_hash: &str) {
+        diesel::delete(blocked_senders::table.filter(blocked_senders::sender_hash.eq(sender_hash)))
+            .execute(self.conn())
+            .expect("Error deleting blocked sender");
+    }
+
+    // Counts a submission dropped by the spam checks, per day.
+    pub fn increment_spam_counter(&self, reason: &str) {
+        let counter = db::NewSpamCounter {
+            day: Local::today().naive_local(),
+            reason: String::from(reason),
+            count: 1,
+        };
+        diesel::insert_into(spam_counters::table)
+            .values(&counter)
+            .on_conflict((spam_counters::day, spam_counters::reason))
+            .do_update()
+            .set(spam_counters::count.eq(spam_counters::count + 1))
+            .execute(self.conn())
+            .expect("Error updating spam counter");
+    }
+
+    // Totals per reason since the day, sorted by reason.
+    pub fn spam_counts_since(&self, day: NaiveDate) -> Vec<(String, i64)> {
+        let mut counts = std::collections::BTreeMap::<String, i64>::new();
+        for counter in spam_counters::table
+            .filter(spam_counters::day.ge(day))
+            .load::<db::SpamCounter>(self.conn())
+            .unwrap()
+        {
+            *counts.entry(counter.reason).or_insert(0) += counter.count as i64;
+        }
+        counts.into_iter().collect()
+    }
+
+    // Labels the question for the classifier. Labelling it again with the
+    // other label replaces the previous one.
+    pub fn train_classifier(&self, question_id: i32, label: classifier::Label) {
+        use diesel::Connection;
+
+        self.conn()
+            .transaction::<_, diesel::result::Error, _>(|| self.train(question_id, label))
+            .expect("Error training classifier");
+    }
+
+    fn train(&self, question_id: i32, label: classifier::Label) -> diesel::QueryResult<()> {
+        let (body, trained_label) = questions::table
+            .filter(questions::id.eq(question_id))
+            .select((questions::body, questions::trained_label))
+            .first::<(String, Option<String>)>(self.conn())?;
+        let trained_label = trained_label.and_then(|l| classifier::Label::parse(&l));
+        if trained_label == Some(label) {
+            return Ok(());
+        }
+
+        let features = classifier::features(&body);
+        if let Some(trained_label) = trained_label {
+            self.add_training(&features, trained_label, -1)?;
+        }
+        self.add_training(&features, label, 1)?;
+        diesel::update(questions::table.filter(questions::id.eq(question_id)))
+            .set(questions::trained_label.eq(Some(label.as_str())))
+            .execute(self.conn())?;
+        Ok(())
+    }
+
+    // Takes the label back, e.g. when a question hidden by mistake is
+    // restored. Does nothing if the question has another label or none.
+    pub fn untrain_classifier(&self, question_id: i32, label: classifier::Label) {
+        use diesel::Connection;
+
+        self.conn()
+            .transaction::<_, diesel::result::Error, _>(|| {
+                let (body, trained_label) = questions::table
+                    .filter(questions::id.eq(question_id))
+                    .select((questions::body, questions::trained_label))
+                    .first::<(String, Option<String>)>(self.conn())?;
+                if trained_label.and_then(|l| classifier::Label::parse(&l)) != Some(label) {
+                    return Ok(());
+                }
+                self.add_training(&classifier::features(&body), label, -1)?;
+                diesel::update(questions::table.filter(questions::id.eq(question_id)))
+                    .set(questions::trained_label.eq(None::<String>))
+                    .execute(self.conn())?;
+                Ok(())
+            })
+            .expect("Error untraining classifier");
+    }
+
+    fn add_training(
+        &self,
+        features: &[String],
+        label: classifier::Label,
+        delta: i32,
+    ) -> diesel::QueryResult<()> {
+        use diesel::pg::upsert::excluded;
+
+        let (spam_count, ham_count) = match label {
+            classifier::Label::Spam => (delta, 0),
+            classifier::Label::Ham => (0, delta),
+        };
+        let tokens = features
+            .iter()
+            .map(|feature| db::NewClassifierToken {
+                token: feature.clone(),
+                spam_count: spam_count,
+                ham_count: ham_count,
+            })
+            .collect::<Vec<_>>();
+        if !tokens.is_empty() {
+            diesel::insert_into(classifier_tokens::table)
+                .values(&tokens)
+                .on_conflict(classifier_tokens::token)
+                .do_update()
+                .set((
+                    classifier_tokens::spam_count
+                        .eq(classifier_tokens::spam_count + excluded(classifier_tokens::spam_count)),
+                    classifier_tokens::ham_count
+                        .eq(classifier_tokens::ham_count + excluded(classifier_tokens::ham_count)),
+                ))
+                .execute(self.conn())?;
+        }
+        diesel::insert_into(classifier_documents::table)
+            .values(&db::NewClassifierDocument {
+                label: String::from(label.as_str()),
+                count: delta,
+            })
+            .on_conflict(classifier_documents::label)
+            .do_update()
+            .set(classifier_documents::count.eq(classifier_documents::count + excluded(classifier_documents::count)))
+            .execute(self.conn())?;
+        Ok(())
+    }
+
+    // None until both labels have classifier::MIN_DOCUMENTS questions.
+    pub fn spam_score(&self, body: &str) -> Option<f64> {
+        let documents = classifier_documents::table
+            .load::<db::ClassifierDocument>(self.conn())
+            .unwrap();
+        let count_of = |label: classifier::Label| {
+            documents
+                .iter()
+                .find(|d| d.label == label.as_str())
+                .map_or(0, |d| d.count)
+        };
+        let (spam_documents, ham_documents) =
+            (count_of(classifier::Label::Spam), count_of(classifier::Label::Ham));
+        if spam_documents < classifier::MIN_DOCUMENTS || ham_documents < classifier::MIN_DOCUMENTS {
+            return None;
+        }
+
+        let features = classifier::features(body);
+        let counts = classifier_tokens::table
+            .filter(classifier_tokens::token.eq_any(&features))
+            .load::<db::ClassifierToken>(self.conn())
+            .unwrap()
+            .into_iter()
+            .map(|t| (t.token, (t.spam_count, t.ham_count)))
+            .collect();
+        Some(classifier::score(&features, &counts, spam_documents, ham_documents))
+    }
+
+    pub fn content_rules(&self) -> Vec<ContentRule> {
+        content_rules::table
+            .order(content_rules::id.asc())
+            .load::<db::ContentRule>(self.conn())
+            .unwrap()
+            .into_iter()
+            .map(|r| self.db2model_content_rule(r))
+            .collect()
+    }
+
+    pub fn store_content_rule(
+        &self,
+        pattern: String,
+        is_regex: bool,
+        action: RuleAction,
+        created_by: String,
+    ) -> ContentRule {
+        let new_rule = db::NewContentRule {
+            pattern: pattern,
+            is_regex: is_regex,
+            action: String::from(action.as_str()),
+            created_by: created_by,
+        };
+        let r: db::ContentRule = diesel::insert_into(content_rules::table)
+            .values(&new_rule)
+            .get_result(self.conn())
+            .expect("Error saving content rule");
+        self.db2model_content_rule(r)
+    }
+
+    // Returns the deleted rule, or None if there is no such rule.
+    pub fn delete_content_rule(&self, id: i32) -> Option<ContentRule> {
+        diesel::delete(content_rules::table.filter(content_rules::id.eq(id)))
+            .get_result::<db::ContentRule>(self.conn())
+            .optional()
+            .unwrap()
+            .map(|r| self.db2model_content_rule(r))
+    }
+
+    pub fn update_answer(&self, answer: Answer, max_length: usize) -> Result<Answer, StoreAnswerError> {
+        if grapheme_count(&answer.body) > max_length {
+            return Err(StoreAnswerError::TooLong {
+                max_length: max_length,
+            });
+        }
+        let a = db::AnswerForm {
+            id: answer.id,
+            question_id: answer.question.id,
+            body: answer.body,
+            created_at: answer.created_at.with_timezone(&Utc),
+        };
+        let a = a.save_changes::<db::Answer>(self.conn()).unwrap();
+        Ok(self.db2model_answer(a, answer.question))
+    }
+
+    pub fn delete_answer(&self, answer_id: i32) -> Option<Answer> {
+        self.find_answer(answer_id).map(|answer| {
+            diesel::delete(answers::table.filter(answers::id.eq(answer.id)))
+                .execute(self.conn())
+                .expect("Error deleting answer");
+            self.set_question_status(answer.question.id, QuestionStatus::Inbox);
+            answer
+        })
+    }
+
+    pub fn set_answer_published(&self, answer_id: i32, published: bool) -> Option<Answer> {
+        let a = diesel::update(answers::table.filter(answers::id.eq(answer_id)))
+            .set(answers::published.eq(published))
+            .get_result::<db::Answer>(self.conn())
+            .optional()
+            .unwrap()?;
+        let question = self.find_question(a.question_id)?;
+        Some(self.db2model_answer(a, question))
+    }
+
+    // Returns the number of reports since `counted_since`, the start of the
+    // current sender hash period: a reporter gets a new hash in each period,
+    // so older reports may come from the same person.
+    pub fn report_answer(
+        &self,
+        answer_id: i32,
+        reason: String,
+        reporter_hash: String,
+        counted_since: DateTime<Utc>,
+    ) -> Result<i64, ReportAnswerError> {
+        if reason.trim().is_empty() {
+            return Err(ReportAnswerError::BlankReason);
+        }
+        if grapheme_count(&reason) > REPORT_REASON_MAX_LENGTH {
+            return Err(ReportAnswerError::TooLong {
+                max_length: REPORT_REASON_MAX_LENGTH,
+            });
+        }
+        // unpublished answers can not be seen, so there is nothing to report
+        let answer = self
+            .find_answer(answer_id)
+            .filter(|answer| answer.published)
+            .ok_or(ReportAnswerError::AnswerNotFound)?;
+        let report = db::NewAnswerReport {
+            answer_id: answer.id,
+            reason: reason,
+            reporter_hash: reporter_hash,
+        };
+        let inserted = diesel::insert_into(answer_reports::table)
+            .values(&report)
+            .on_conflict((answer_reports::answer_id, answer_reports::reporter_hash))
+            .do_nothing()
+            .execute(self.conn())
+            .expect("Error saving answer report");
+        if inserted == 0 {
+            return Err(ReportAnswerError::AlreadyReported);
+        }
+        Ok(answer_reports::table
+            .filter(answer_reports::answer_id.eq(answer.id))
+            .filter(answer_reports::created_at.ge(counted_since))
+            .count()
+            .get_result(self.conn())
+            .unwrap())
+    }
+
+    pub fn report_count(&self, answer_id: i32) -> i64 {
+        answer_reports::table
+            .filter(answer_reports::answer_id.eq(answer_id))
+            .count()
+            .get_result(self.conn())
+            .unwrap()
+    }
+
+    // Newest first.
+    pub fn answer_reports(&self) -> Vec<AnswerReport> {
+        answer_reports::table
+            .inner_join(answers::table.inner_join(questions::table))
+            .order(answer_reports::created_at.desc())
+            .load::<(db::AnswerReport, (db::Answer, db::Question))>(self.conn())
+            .unwrap()
+            .into_iter()
+            .map(|(r, (a, q))| AnswerReport {
+                id: r.id,
+                reason: r.reason,
+                created_at: r.created_at.with_timezone(&Local),
+                answer: self.db2model_answer(a, self.db2model_question(q)),
+            })
+            .collect()
+    }
+
+    // Returns the number of deleted reports.
+    pub fn delete_answer_reports(&self, answer_id: i32) -> usize {
+        diesel::delete(answer_reports::table.filter(answer_reports::answer_id.eq(answer_id)))
+            .execute(self.conn())
+            .expect("Error deleting answer reports")
+    }
+
+    pub fn store_webhook_delivery(
+        &self,
+        event: String,
+        url: String,
+        payload: String,
+        result: Result<i32, String>,
+    ) {
+        let (status_code, error) = match result {
+            Ok(status_code) => (Some(status_code), None),
+            Err(error) => (None, Some(error)),
+        };
+        let new_delivery = db::NewWebhookDelivery {
+            event: event,
+            url: url,
+            payload: payload,
+            status_code: status_code,
+            error: error,
+        };
+        diesel::insert_into(webhook_deliveries::table)
+            .values(&new_delivery)
+            .execute(self.conn())
+            .expect("Error saving webhook delivery");
+    }
+
+    pub fn webhook_deliveries(&self, count: i64) -> Vec<WebhookDelivery> {
+        webhook_deliveries::table
+            .order(webhook_deliveries::delivered_at.desc())
+            .limit(count)
+            .load::<db::WebhookDelivery>(self.conn())
+            .unwrap()
+            .into_iter()
+            .map(|d| WebhookDelivery {
+                id: d.id,
+                event: d.event,
+                url: d.url,
+                payload: d.payload,
+                status_code: d.status_code,
+                error: d.error,
+                delivered_at: d.delivered_at.with_timezone(&Local),
+            })
+            .collect()
+    }
+
+    // result: Ok((remote_id, remote_url)) or Err(error message)
+    pub fn store_cross_post(
+        &self,
+        answer_id: i32,
+        service: &str,
+        result: Result<(String, String), String>,
+    ) -> CrossPost {
+        let (remote_id, remote_url, error) = match result {
+            // posted, but the link is not safe to show
+            Ok((remote_id, remote_url)) if !utils::is_http_url(&remote_url) => {
+                (Some(remote_id), None, None)
+            }
+            Ok((remote_id, remote_url)) => (Some(remote_id), Some(remote_url), None),
+            Err(error) => (None, None, Some(error)),
+        };
+        let new_cross_post = db::NewCrossPost {
+            answer_id: answer_id,
+            service: String::from(service),
+            remote_id: remote_id,
+            remote_url: remote_url,
+            error: error,
+            updated_at: Utc::now(),
+        };
+        let c: db::CrossPost = diesel::insert_into(cross_posts::table)
+            .values(&new_cross_post)
+            .on_conflict((cross_posts::answer_id, cross_posts::service))
+            .do_update()
+            .set(&new_cross_post)
+            .get_result(self.conn())
+            .expect("Error saving cross post");
+        self.db2model_cross_post(c)
+    }
+
+    pub fn cross_posts(&self, answer_id: i32) -> Vec<CrossPost> {
+        cross_posts::table
+            .filter(cross_posts::answer_id.eq(answer_id))
+            .order(cross_posts::service.asc())
+            .load::<db::CrossPost>(self.conn())
+            .unwrap()
+            .into_iter()
+            .map(|c| self.db2model_cross_post(c))
+            .collect()
+    }
+
+    pub fn store_audit_event(
+        &self,
+        actor: String,
+        action: String,
+        target_id: String,
+        before_value: Option<String>,
+        after_value: Option<String>,
+    ) {
+        let new_event = db::NewAuditEvent {
+            actor: actor,
+            action: action,
+            target_id: target_id,
+            before_value: before_value,
+            after_value: after_value,
+        };
+        diesel::insert_into(audit_events::table)
+            .values(&new_event)
+            .execute(self.conn())
+            .expect("Error saving audit event");
+    }
+
+    pub fn audit_events(&self, filter: &AuditEventFilter, count: i64) -> Vec<AuditEvent> {
+        let mut query = audit_events::table.into_boxed();
+        if let Some(ref action) = filter.action {
+            query = query.filter(audit_events::action.eq(action.clone()));
+        }
+        if let Some(from) = filter.from {
+            query = query.filter(audit_events::created_at.ge(from.with_timezone(&Utc)));
+        }
+        if let Some(to) = filter.to {
+            query = query.filter(audit_events::created_at.lt(to.with_timezone(&Utc)));
+        }
+        query
+            .order(audit_events::id.desc())
+            .limit(count)
+            .load::<db::AuditEvent>(self.conn())
+            .unwrap()
+            .into_iter()
+            .map(|e| AuditEvent {
+                id: e.id,
+                actor: e.actor,
+                action: e.action,
+                target_id: e.target_id,
+                before_value: e.before_value,
+                after_value: e.after_value,
+                created_at: e.created_at.with_timezone(&Local),
+            })
+            .collect()
+    }
+
+    pub fn find_admin(&self, username: &str) -> Option<Admin> {
+        admins::table
+            .filter(admins::username.eq(username))
+            .first::<db::Admin>(self.conn())
+            .optional()
+            .unwrap()
+            .map(|a| self.db2model_admin(a))
+    }
+
+    pub fn admins(&self) -> Vec<Admin> {
+        admins::table
+            .order(admins::created_at.asc())
+            .load::<db::Admin>(self.conn())
+            .unwrap()
+            .into_iter()
+            .map(|a| self.db2model_admin(a))
+            .collect()
+    }
+
+    pub fn create_admin(&self, username: String, password_hash: String, role: Role) {
+        let new_admin = db::NewAdmin {
+            username: username,
+            password_hash: password_hash,
+            role: String::from(role.as_str()),
+        };
+        diesel::insert_into(admins::table)
+            .values(&new_admin)
+            .execute(self.conn())
+            .expect("Error saving new admin");
+    }
+
+    pub fn update_admin_password(&self, username: &str, password_hash: String) {
+        diesel::update(admins::table.filter(admins::username.eq(username)))
+            .set((
+                admins::password_hash.eq(password_hash),
+                admins::updated_at.eq(Utc::now()),
+            ))
+            .execute(self.conn())
+            .expect("Error updating admin password");
+    }
+
+    // Both of update_admin_role and delete_admin refuse to leave the site without an owner.
+    pub fn update_admin_role(&self, username: &str, role: Role) -> Result<(), AdminError> {
+        self.ensure_other_owner_exists(username, role)?;
+        diesel::update(admins::table.filter(admins::username.eq(username)))
+            .set((
+                admins::role.eq(role.as_str()),
+                admins::updated_at.eq(Utc::now()),
+            ))
+            .execute(self.conn())
+            .expect("Error updating admin role");
+        Ok(())
+    }
+
+    pub fn delete_admin(&self, username: &str) -> Result<(), AdminError> {
+        use diesel::Connection;
+
+        self.ensure_other_owner_exists(username, Role::Moderator)?;
+        self.conn()
+            .transaction::<_, diesel::result::Error, _>(|| {
+                diesel::delete(admin_recovery_codes::table.filter(admin_recovery_codes::username.eq(username)))
+                    .execute(self.conn())?;
+                diesel::delete(admins::table.filter(admins::username.eq(username)))
+                    .execute(self.conn())?;
+                Ok(())
+            })
+            .expect("Error deleting admin");
+        Ok(())
+    }
+
+    fn ensure_other_owner_exists(&self, username: &str, new_role: Role) -> Result<(), AdminError> {
+        if new_role == Role::Owner {
+            return Ok(());
+        }
+        let other_owners = admins::table
+            .filter(admins::role.eq(Role::Owner.as_str()))
+            .filter(admins::username.ne(username))
+            .count()
+            .get_result::<i64>(self.conn())
+            .unwrap();
+        if other_owners == 0 {
+            return Err(AdminError::LastOwner);
+        }
+        Ok(())
+    }
+
+    pub fn store_api_token(
+        &self,
+        username: String,
+        name: String,
+        token_hash: String,
+        scope: TokenScope,
+    ) -> ApiToken {
+        let new_token = db::NewApiToken {
+            username: username,
+            name: name,
+            token_hash: token_hash,
+            scope: String::from(scope.as_str()),
+        };
+        let t: db::ApiToken = diesel::insert_into(api_tokens::table)
+            .values(&new_token)
+            .get_result(self.conn())
+            .expect("Error saving api token");
+        self.db2model_api_token(t)
+    }
+
+    pub fn api_tokens(&self, username: &str) -> Vec<ApiToken> {
+        api_tokens::table
+            .filter(api_tokens::username.eq(username))
+            .order(api_tokens::id.desc())
+            .load::<db::ApiToken>(self.conn())
+            .unwrap()
+            .into_iter()
+            .map(|t| self.db2model_api_token(t))
+            .collect()
+    }
+
+    // Finds an unrevoked token and records that it has been used.
+    pub fn use_api_token(&self, token_hash: &str) -> Option<ApiToken> {
+        diesel::update(
+            api_tokens::table
+                .filter(api_tokens::token_hash.eq(token_hash))
+                .filter(api_tokens::revoked_at.is_null()),
+        )
+        .set(api_tokens::last_used_at.eq(Some(Utc::now())))
+        .get_result::<db::ApiToken>(self.conn())
+        .optional()
+        .unwrap()
+        .map(|t| self.db2model_api_token(t))
+    }
+
+    // Returns the revoked token, or None if the admin has no such active token.
+    pub fn revoke_api_token(&self, username: &str, id: i32) -> Option<ApiToken> {
+        diesel::update(
+            api_tokens::table
+                .filter(api_tokens::id.eq(id))
+                .filter(api_tokens::username.eq(username))
+                .filter(api_tokens::revoked_at.is_null()),
+        )
+        .set(api_tokens::revoked_at.eq(Some(Utc::now())))
+        .get_result::<db::ApiToken>(self.conn())
+        .optional()
+        .unwrap()
+        .map(|t| self.db2model_api_token(t))
+    }
+
+    // Stores a secret which is not used for login until enable_totp is called.
+    pub fn store_totp_secret(&self, username: &str, secret: String) {
+        diesel::update(admins::table.filter(admins::username.eq(username)))
+            .set((
+                admins::totp_secret.eq(Some(secret)),
+                admins::totp_enabled.eq(false),
+            ))
+            .execute(self.conn())
+            .expect("Error saving totp secret");
+    }
+
+    pub fn enable_totp(&self, username: &str, recovery_code_hashes: Vec<String>) {
+        use diesel::Connection;
+
+        let new_codes = recovery_code_hashes
+            .into_iter()
+            .map(|code_hash| db::NewAdminRecoveryCode {
+                username: String::from(username),
+                code_hash: code_hash,
+            })
+            .collect::<Vec<_>>();
+        self.conn()
+            .transaction::<_, diesel::result::Error, _>(|| {
+                diesel::update(admins::table.filter(admins::username.eq(username)))
+                    .set(admins::totp_enabled.eq(true))
+                    .execute(self.conn())?;
+                diesel::delete(admin_recovery_codes::table.filter(admin_recovery_codes::username.eq(username)))
+                    .execute(self.conn())?;
+                diesel::insert_into(admin_recovery_codes::table)
+                    .values(&new_codes)
+                    .execute(self.conn())?;
+                Ok(())
+            })
+            .expect("Error enabling totp");
+    }
+
+    pub fn disable_totp(&self, username: &str) {
+        use diesel::Connection;
+
+        self.conn()
+            .transaction::<_, diesel::result::Error, _>(|| {
+                diesel::update(admins::table.filter(admins::username.eq(username)))
+                    .set((
+                        admins::totp_secret.eq(None::<String>),
+                        admins::totp_enabled.eq(false),
+                    ))
+                    .execute(self.conn())?;
+                diesel::delete(admin_recovery_codes::table.filter(admin_recovery_codes::username.eq(username)))
+                    .execute(self.conn())?;
+                Ok(())
+            })
+            .expect("Error disabling totp");
+    }
+
+    // Marks the recovery code as used. Returns false if it does not exist or is already used.
+    pub fn use_recovery_code(&self, username: &str, code_hash: &str) -> bool {
+        let updated = diesel::update(
+            admin_recovery_codes::table
+                .filter(admin_recovery_codes::username.eq(username))
+                .filter(admin_recovery_codes::code_hash.eq(code_hash))
+                .filter(admin_recovery_codes::used_at.is_null()),
+        )
+        .set(admin_recovery_codes::used_at.eq(Some(Utc::now())))
+        .execute(self.conn())
+        .expect("Error using recovery code");
+        updated > 0
+    }
+
+    // Accepts a TOTP time step only once, so that an observed code cannot be
+    // replayed. Returns false if the step or a later one was already used.
+    pub fn use_totp_step(&self, username: &str, step: i64) -> bool {
+        use diesel::BoolExpressionMethods;
+
+        let updated = diesel::update(
+            admins::table
+                .filter(admins::username.eq(username))
+                .filter(admins::totp_last_step.is_null().or(admins::totp_last_step.lt(step))),
+        )
+        .set(admins::totp_last_step.eq(Some(step)))
+        .execute(self.conn())
+        .expect("Error using totp step");
+        updated > 0
+    }
+
+    // Counts a wrong second factor. Once `max_attempts` are reached in a row,
+    // locks the second factor until `locked_until` and returns true.
+    pub fn record_totp_failure(
+        &self,
+        username: &str,
+        max_attempts: i32,
+        locked_until: DateTime<Local>,
+    ) -> bool {
+        let attempts = diesel::update(admins::table.filter(admins::username.eq(username)))
+            .set(admins::totp_failed_attempts.eq(admins::totp_failed_attempts + 1))
+            .returning(admins::totp_failed_attempts)
+            .get_result::<i32>(self.conn())
+            .expect("Error counting totp failure");
+        if attempts < max_attempts {
+            return false;
+        }
+        diesel::update(admins::table.filter(admins::username.eq(username)))
+            .set((
+                admins::totp_failed_attempts.eq(0),
+                admins::totp_locked_until.eq(Some(locked_until.with_timezone(&Utc))),
+            ))
+            .execute(self.conn())
+            .expect("Error locking totp");
+        true
+    }
+
+    pub fn reset_totp_failures(&self, username: &str) {
+        diesel::update(admins::table.filter(admins::username.eq(username)))
+            .set((
+                admins::totp_failed_attempts.eq(0),
+                admins::totp_locked_until.eq(None::<DateTime<Utc>>),
+            ))
+            .execute(self.conn())
+            .expect("Error resetting totp failures");
+    }
+
+    fn db2model_admin(&self, a: db::Admin) -> Admin {
+        Admin {
+            username: a.username,
+            password_hash: a.password_hash,
+            totp_secret: a.totp_secret,
+            totp_enabled: a.totp_enabled,
+            totp_locked_until: a.totp_locked_until.map(|d| d.with_timezone(&Local)),
+            role: Role::parse(&a.role).expect("Unknown admin role"),
+            created_at: a.created_at.with_timezone(&Local),
+        }
+    }
+
+    fn db2model_api_token(&self, t: db::ApiToken) -> ApiToken {
+        ApiToken {
+            id: t.id,
+            username: t.username,
+            name: t.name,
+            scope: TokenScope::parse(&t.scope).expect("Unknown token scope"),
+            created_at: t.created_at.with_timezone(&Local),
+            last_used_at: t.last_used_at.map(|d| d.with_timezone(&Local)),
+            revoked_at: t.revoked_at.map(|d| d.with_timezone(&Local)),
+        }
+    }
+
+    fn db2model_cross_post(&self, c: db::CrossPost) -> CrossPost {
+        CrossPost {
+            service: c.service,
+            remote_id: c.remote_id,
+            remote_url: c.remote_url,
+            error: c.error,
+            updated_at: c.updated_at.with_timezone(&Local),
+        }
     }
 
     fn db2model_question(&self, q: db::Question) -> Question {
@@ -260,7 +1861,21 @@ impl Repository {
             id: q.id,
             body: q.body,
             created_at: q.created_at.with_timezone(&Local),
-            hidden: q.hidden,
+            status: QuestionStatus::parse(&q.status).expect("Unknown question status"),
+            sender_hash: q.sender_hash,
+            received_count: q.received_count,
+            spam_score: q.spam_score,
+        }
+    }
+
+    fn db2model_content_rule(&self, r: db::ContentRule) -> ContentRule {
+        ContentRule {
+            id: r.id,
+            pattern: r.pattern,
+            is_regex: r.is_regex,
+            action: RuleAction::parse(&r.action).expect("Unknown content rule action"),
+            created_by: r.created_by,
+            created_at: r.created_at.with_timezone(&Local),
         }
     }
 
@@ -269,6 +1884,7 @@ impl Repository {
             id: a.id,
             body: a.body,
             created_at: a.created_at.with_timezone(&Local),
+            published: a.published,
             question: q,
         }
     }
@@ -2,27 +2,120 @@ use lettre::{SmtpTransport, SmtpClient, Transport};
 use lettre_email::EmailBuilder;
 use lettre::smtp::authentication::{Mechanism, Credentials};
 use model;
+use rocket_contrib::templates::tera::Tera;
+use serde::Serialize;
 use std::thread;
 use std::env;
 
-pub fn send_email(question: model::Question) {
+const TEMPLATE_GLOB: &'static str = "templates/email/**/*.tera";
+const DEFAULT_LANGUAGE: &'static str = "ja";
+
+#[derive(Serialize, Debug)]
+struct NewQuestionMailDTO {
+    pub question_body: String,
+    pub question_url: String,
+}
+
+#[derive(Serialize, Debug)]
+struct AnswerReportMailDTO {
+    pub answer_body: String,
+    pub question_body: String,
+    pub reason: String,
+    pub report_count: i64,
+    pub unpublished: bool,
+    pub reports_url: String,
+}
+
+pub struct Notifier {
+    templates: Tera,
+    language: String,
+    new_question_subject: Option<String>,
+}
+
+impl Notifier {
+    pub fn from_env() -> Self {
+        let mut templates = Tera::new(TEMPLATE_GLOB).expect("Failed to load email templates.");
+        templates.autoescape_on(vec!["html.tera"]);
+
+        let language = env::var("MAILER_LANGUAGE").unwrap_or(String::from(DEFAULT_LANGUAGE));
+        for mail in &["new_question", "answer_report"] {
+            for part in &["subject", "html", "text"] {
+                let name = template_name(&language, mail, part);
+                if let Err(_) = templates.get_template(&name) {
+                    panic!("Email template {} not found.", name);
+                }
+            }
+        }
+
+        Self {
+            templates: templates,
+            language: language,
+            new_question_subject: env::var("MAILER_NEW_QUESTION_SUBJECT").ok(),
+        }
+    }
+
+    pub fn send_new_question(&self, question: model::Question) {
+        let context = NewQuestionMailDTO {
+            question_body: question.body,
+            question_url: format!(
+                "https://{}/admin/question/{}",
+                env::var("APPLICATION_DOMAIN").expect("APPLICATION_DOMAIN not specified"),
+                question.id
+            ),
+        };
+        let subject = match self.new_question_subject {
+            Some(ref subject) => subject.clone(),
+            None => self.render("new_question", "subject", &context).trim().to_string(),
+        };
+        let html = self.render("new_question", "html", &context);
+        let text = self.render("new_question", "text", &context);
+        send_email(subject, html, text);
+    }
+
+    pub fn send_answer_report(
+        &self,
+        answer: model::Answer,
+        reason: String,
+        report_count: i64,
+    ) {
+        let context = AnswerReportMailDTO {
+            answer_body: answer.body,
+            question_body: answer.question.body,
+            reason: reason,
+            report_count: report_count,
+            unpublished: !answer.published,
+            reports_url: format!(
+                "https://{}/admin/reports",
+                env::var("APPLICATION_DOMAIN").expect("APPLICATION_DOMAIN not specified"),
+            ),
+        };
+        let subject = self.render("answer_report", "subject", &context).trim().to_string();
+        let html = self.render("answer_report", "html", &context);
+        let text = self.render("answer_report", "text", &context);
+        send_email(subject, html, text);
+    }
+
+    fn render<T: Serialize>(&self, mail: &str, part: &str, context: &T) -> String {
+        let name = template_name(&self.language, mail, part);
+        self.templates
+            .render(&name, context)
+            .expect(&format!("Failed to render email template {}.", name))
+    }
+}
+
+// Templates are laid out as `templates/email/<language>/<mail>/<part>.tera`.
+fn template_name(language: &str, mail: &str, part: &str) -> String {
+    format!("{}/{}/{}.tera", language, mail, part)
+}
+
+fn send_email(subject: String, html: String, text: String) {
     let builder = thread::Builder::new().name("send-email-thread".into());
     builder.spawn(move || {
-        let question_url = format!(
-            "https://{}/admin/question/{}",
-            env::var("APPLICATION_DOMAIN").expect("APPLICATION_DOMAIN not specified"),
-            question.id
-        );
         let email = EmailBuilder::new()
             .to(env::var("ADMIN_EMAIL").expect("ADMIN_EMAIL not specified"))
             .from(env::var("MAILER_FROM").expect("MAILER_FROM not specified"))
-            .subject("質問が投稿されました")
-            .html(
-                format!(
-                    "<p>質問が投稿されました</p><p>{}</p><p><a href='{}'>{}</a>",
-                    htmlescape::encode_minimal(&question.body), question_url, question_url
-                )
-            )
+            .subject(subject)
+            .alternative(html, text)
             .build()
             .expect("Failed to build email.");
 
@@ -0,0 +1,37 @@
+use argon2::{self, Config, Variant};
+use rand::Rng;
+
+// OWASP recommended minimum for Argon2id (19 MiB, 2 iterations)
+fn config() -> Config<'static> {
+    Config {
+        variant: Variant::Argon2id,
+        mem_cost: 19 * 1024,
+        time_cost: 2,
+        ..Config::default()
+    }
+}
+
+#[test]
+fn hash_and_verify_test() {
+    let encoded = hash("correct horse battery staple");
+    assert!(encoded.starts_with("$argon2id$"));
+    assert!(verify(&encoded, "correct horse battery staple"));
+    assert!(!verify(&encoded, "Tr0ub4dor&3"));
+}
+
+pub fn hash(password: &str) -> String {
+    let salt = rand::thread_rng().gen::<[u8; 16]>();
+    argon2::hash_encoded(password.as_bytes(), &salt, &config()).expect("Failed to hash password.")
+}
+
+// argon2::verify_encoded compares the hashes in constant time.
+pub fn verify(encoded: &str, password: &str) -> bool {
+    argon2::verify_encoded(encoded, password.as_bytes()).unwrap_or(false)
+}
+
+// Spends about the same time as `verify` so that unknown usernames
+// cannot be told apart from wrong passwords by response time.
+pub fn verify_nothing(password: &str) -> bool {
+    hash(password);
+    false
+}
@@ -0,0 +1,87 @@
+use std::env;
+use std::sync::{Arc, RwLock};
+use std::thread;
+use std::time::Duration;
+use twitter;
+
+const DEFAULT_NAME: &'static str = "Reing";
+const DEFAULT_IMAGE_URL: &'static str = "/static/image/profile.jpg";
+const DEFAULT_REFRESH_INTERVAL_SECS: u64 = 60 * 60;
+
+#[derive(Clone, Debug)]
+pub struct Profile {
+    pub name: String,
+    pub image_url: String,
+}
+
+#[derive(Clone)]
+pub struct UserProfile {
+    current: Arc<RwLock<Profile>>,
+}
+
+impl UserProfile {
+    // Resolves the profile once, then keeps refreshing it from the Twitter API
+    // in the background when TWITTER_SCREEN_NAME is set.
+    pub fn resolve() -> Self {
+        let screen_name = env::var("TWITTER_SCREEN_NAME").ok();
+        let twitter = twitter::Client::from_env();
+        let fetched = match (&screen_name, &twitter) {
+            (Some(screen_name), Some(twitter)) => fetch(twitter, screen_name),
+            _ => None,
+        };
+        let user_profile = Self {
+            current: Arc::new(RwLock::new(Profile {
+                name: fetched
+                    .as_ref()
+                    .map(|p| p.name.clone())
+                    .or(env::var("PROFILE_USERNAME").ok())
+                    .unwrap_or(String::from(DEFAULT_NAME)),
+                image_url: fetched
+                    .as_ref()
+                    .map(|p| p.image_url.clone())
+                    .or(env::var("PROFILE_IMAGE_URL").ok())
+                    .unwrap_or(String::from(DEFAULT_IMAGE_URL)),
+            })),
+        };
+
+        if let (Some(screen_name), Some(twitter)) = (screen_name, twitter) {
+            user_profile.spawn_refresher(twitter, screen_name);
+        }
+        user_profile
+    }
+
+    pub fn get(&self) -> Profile {
+        self.current.read().unwrap().clone()
+    }
+
+    fn spawn_refresher(&self, twitter: twitter::Client, screen_name: String) {
+        let interval = env::var("PROFILE_REFRESH_INTERVAL_SECS")
+            .ok()
+            .and_then(|secs| secs.parse().ok())
+            .unwrap_or(DEFAULT_REFRESH_INTERVAL_SECS);
+        let current = self.current.clone();
+
+        let builder = thread::Builder::new().name("refresh-profile-thread".into());
+        builder.spawn(move || loop {
+            thread::sleep(Duration::from_secs(interval));
+            // keep the last known profile when the API is unavailable
+            if let Some(profile) = fetch(&twitter, &screen_name) {
+                *current.write().unwrap() = profile;
+            }
+        }).unwrap();
+    }
+}
+
+fn fetch(twitter: &twitter::Client, screen_name: &str) -> Option<Profile> {
+    match twitter.show_user(screen_name) {
+        Ok(user) => Some(Profile {
+            name: user.name,
+            // "_normal" is 48x48, which is too small for the top page
+            image_url: user.profile_image_url_https.replace("_normal.", "_400x400."),
+        }),
+        Err(err) => {
+            println!("Failed to fetch twitter profile: {}", err);
+            None
+        }
+    }
+}
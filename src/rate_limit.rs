@@ -0,0 +1,124 @@
+use std::collections::HashMap;
+use std::env;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+const DEFAULT_BURST: u32 = 5;
+const DEFAULT_REFILL_SECS: u64 = 60;
+// forget idle senders once this many are tracked
+const MAX_TRACKED_KEYS: usize = 10_000;
+
+struct Bucket {
+    tokens: f64,
+    updated_at: Instant,
+}
+
+// Token bucket per key, kept in memory. Each key may post `burst` times in a
+// row and gets one more chance every `refill` afterwards.
+pub struct RateLimiter {
+    burst: f64,
+    refill: Duration,
+    buckets: Mutex<HashMap<String, Bucket>>,
+}
+
+#[test]
+fn check_at_test() {
+    let limiter = RateLimiter::new(2, Duration::from_secs(10));
+    let now = Instant::now();
+    assert!(limiter.check_at("a", now));
+    assert!(limiter.check_at("a", now));
+    assert!(!limiter.check_at("a", now));
+    // other keys are independent
+    assert!(limiter.check_at("b", now));
+    assert!(!limiter.check_at("a", now + Duration::from_secs(9)));
+    // never refills beyond the burst
+    let later = now + Duration::from_secs(60);
+    assert!(limiter.check_at("a", later));
+    assert!(limiter.check_at("a", later));
+    assert!(!limiter.check_at("a", later));
+}
+
+#[test]
+fn is_suspicious_at_test() {
+    let limiter = RateLimiter::new(4, Duration::from_secs(10));
+    let now = Instant::now();
+    assert!(!limiter.is_suspicious_at("a", now));
+    assert!(limiter.check_at("a", now));
+    assert!(!limiter.is_suspicious_at("a", now));
+    assert!(limiter.check_at("a", now));
+    assert!(limiter.is_suspicious_at("a", now));
+    assert!(!limiter.is_suspicious_at("a", now + Duration::from_secs(10)));
+}
+
+impl RateLimiter {
+    pub fn new(burst: u32, refill: Duration) -> Self {
+        Self {
+            burst: burst as f64,
+            refill: refill,
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn from_env() -> Self {
+        Self::from_env_vars("QUESTION_RATE_BURST", "QUESTION_RATE_REFILL_SECS")
+    }
+
+    pub fn from_env_vars(burst_var: &str, refill_secs_var: &str) -> Self {
+        let burst = env::var(burst_var)
+            .ok()
+            .and_then(|burst| burst.parse().ok())
+            .unwrap_or(DEFAULT_BURST);
+        let refill_secs = env::var(refill_secs_var)
+            .ok()
+            .and_then(|secs| secs.parse().ok())
+            .unwrap_or(DEFAULT_REFILL_SECS);
+        Self::new(burst, Duration::from_secs(refill_secs))
+    }
+
+    // Consumes a token. Returns false if the key is over the limit.
+    pub fn check(&self, key: &str) -> bool {
+        self.check_at(key, Instant::now())
+    }
+
+    // True once the key has used up half of its burst. Does not consume a token.
+    pub fn is_suspicious(&self, key: &str) -> bool {
+        self.is_suspicious_at(key, Instant::now())
+    }
+
+    fn is_suspicious_at(&self, key: &str, now: Instant) -> bool {
+        let buckets = self.buckets.lock().unwrap();
+        match buckets.get(key) {
+            Some(bucket) => refilled(bucket, self.burst, self.refill, now) <= self.burst / 2.0,
+            None => false,
+        }
+    }
+
+    fn check_at(&self, key: &str, now: Instant) -> bool {
+        let mut buckets = self.buckets.lock().unwrap();
+        if buckets.len() >= MAX_TRACKED_KEYS {
+            let (burst, refill) = (self.burst, self.refill);
+            buckets.retain(|_, bucket| refilled(bucket, burst, refill, now) < burst);
+        }
+
+        let bucket = buckets.entry(String::from(key)).or_insert(Bucket {
+            tokens: self.burst,
+            updated_at: now,
+        });
+        bucket.tokens = refilled(bucket, self.burst, self.refill, now);
+        bucket.updated_at = now;
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+fn refilled(bucket: &Bucket, burst: f64, refill: Duration, now: Instant) -> f64 {
+    if refill.as_secs_f64() <= 0.0 {
+        return burst;
+    }
+    let elapsed = now.duration_since(bucket.updated_at);
+    (bucket.tokens + elapsed.as_secs_f64() / refill.as_secs_f64()).min(burst)
+}
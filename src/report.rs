@@ -0,0 +1,52 @@
+use rate_limit::RateLimiter;
+use std::env;
+use std::time::Duration;
+
+// Reports on published answers by visitors.
+pub struct AnswerReports {
+    limiter: RateLimiter,
+    // unpublish answers reported by at least this many senders within one
+    // sender hash period, if set
+    unpublish_threshold: Option<i64>,
+}
+
+#[test]
+fn should_unpublish_test() {
+    let limiter = || RateLimiter::new(5, Duration::from_secs(60));
+    let reports = AnswerReports::new(limiter(), Some(3));
+    assert!(!reports.should_unpublish(2));
+    assert!(reports.should_unpublish(3));
+    let reports = AnswerReports::new(limiter(), None);
+    assert!(!reports.should_unpublish(100));
+}
+
+impl AnswerReports {
+    pub fn new(limiter: RateLimiter, unpublish_threshold: Option<i64>) -> Self {
+        Self {
+            limiter: limiter,
+            unpublish_threshold: unpublish_threshold,
+        }
+    }
+
+    pub fn from_env() -> Self {
+        Self::new(
+            RateLimiter::from_env_vars("ANSWER_REPORT_RATE_BURST", "ANSWER_REPORT_RATE_REFILL_SECS"),
+            env::var("ANSWER_REPORT_UNPUBLISH_THRESHOLD")
+                .ok()
+                .and_then(|threshold| threshold.parse().ok())
+                .filter(|threshold| *threshold > 0),
+        )
+    }
+
+    // Consumes a token. Returns false if the reporter is over the limit.
+    pub fn check_rate(&self, reporter_hash: &str) -> bool {
+        self.limiter.check(reporter_hash)
+    }
+
+    pub fn should_unpublish(&self, report_count: i64) -> bool {
+        match self.unpublish_threshold {
+            Some(threshold) => report_count >= threshold,
+            None => false,
+        }
+    }
+}
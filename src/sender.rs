@@ -0,0 +1,97 @@
+use chrono::prelude::*;
+use hex;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::env;
+
+const DEFAULT_ROTATION_SECS: i64 = 7 * 24 * 60 * 60;
+
+// What to do with questions from blocked senders.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BlockMode {
+    // show an error page
+    Reject,
+    // pretend to accept, but hide the question without notification
+    Shadow,
+}
+
+impl BlockMode {
+    pub fn from_env() -> Self {
+        match env::var("BLOCKED_SENDER_MODE") {
+            Ok(ref mode) if mode == "shadow" => BlockMode::Shadow,
+            Ok(ref mode) if mode != "reject" => panic!("Unknown BLOCKED_SENDER_MODE: {}", mode),
+            _ => BlockMode::Reject,
+        }
+    }
+}
+
+// Identifies the sender of a question without keeping the raw IP address.
+// The hash changes every rotation period, so senders can only be linked
+// within one period.
+pub struct SenderHasher {
+    secret: Vec<u8>,
+    rotation_secs: i64,
+}
+
+#[test]
+fn hash_at_test() {
+    let hasher = SenderHasher::new(b"secret".to_vec(), 100);
+    assert_eq!(hasher.hash_at("192.0.2.1", 100), hasher.hash_at("192.0.2.1", 199));
+    assert_ne!(hasher.hash_at("192.0.2.1", 100), hasher.hash_at("192.0.2.1", 200));
+    assert_ne!(hasher.hash_at("192.0.2.1", 100), hasher.hash_at("192.0.2.2", 100));
+}
+
+#[test]
+fn period_of_test() {
+    let hasher = SenderHasher::new(b"secret".to_vec(), 100);
+    let (start, end) = hasher.period_of(Utc.timestamp(150, 0));
+    assert_eq!(start, Utc.timestamp(100, 0));
+    assert_eq!(end, Utc.timestamp(200, 0));
+}
+
+impl SenderHasher {
+    pub fn new(secret: Vec<u8>, rotation_secs: i64) -> Self {
+        Self {
+            secret: secret,
+            rotation_secs: rotation_secs.max(1),
+        }
+    }
+
+    // The secret is required: with a random one, hashes would change on
+    // every restart and blocks would stop matching anyone.
+    pub fn from_env() -> Self {
+        let secret = env::var("SENDER_HASH_SECRET")
+            .expect("SENDER_HASH_SECRET must be set")
+            .into_bytes();
+        let rotation_secs = env::var("SENDER_HASH_ROTATION_SECS")
+            .ok()
+            .and_then(|secs| secs.parse().ok())
+            .unwrap_or(DEFAULT_ROTATION_SECS);
+        Self::new(secret, rotation_secs)
+    }
+
+    pub fn hash(&self, ip_address: &str) -> String {
+        self.hash_at(ip_address, Utc::now().timestamp())
+    }
+
+    // The hash in the previous period, to carry blocks over the rotation.
+    pub fn previous_hash(&self, ip_address: &str) -> String {
+        self.hash_at(ip_address, Utc::now().timestamp() - self.rotation_secs)
+    }
+
+    // The period the time belongs to, as [start, end).
+    pub fn period_of(&self, at: DateTime<Utc>) -> (DateTime<Utc>, DateTime<Utc>) {
+        let start = at.timestamp() - at.timestamp().rem_euclid(self.rotation_secs);
+        (
+            Utc.timestamp(start, 0),
+            Utc.timestamp(start + self.rotation_secs, 0),
+        )
+    }
+
+    fn hash_at(&self, ip_address: &str, unixtime: i64) -> String {
+        let period = unixtime / self.rotation_secs;
+        let mut mac = Hmac::<Sha256>::new_varkey(&self.secret).expect("invalid key length");
+        mac.input(format!("{}:{}", period, ip_address).as_bytes());
+        hex::encode(mac.result().code())
+    }
+}
@@ -0,0 +1,65 @@
+use hex;
+use hmac::{Hmac, Mac};
+use rand::Rng;
+use sha2::Sha256;
+use std::env;
+use utils;
+
+// Signs short payloads handed to browsers, so they can be verified when
+// they come back without keeping any state on the server. The purpose is
+// signed along with the payload, so a token issued for one use is rejected
+// by another.
+#[derive(Clone)]
+pub struct Signer {
+    secret: Vec<u8>,
+}
+
+#[test]
+fn verify_test() {
+    let signer = Signer::new(b"secret".to_vec());
+    let token = signer.sign("test", "1234.abc");
+    assert_eq!(signer.verify("test", &token), Some(String::from("1234.abc")));
+    assert_eq!(signer.verify("test", &token.replace("1234", "1235")), None);
+    assert_eq!(signer.verify("other", &token), None);
+    assert_eq!(Signer::new(b"other".to_vec()).verify("test", &token), None);
+    assert_eq!(signer.verify("test", "1234"), None);
+}
+
+impl Signer {
+    pub fn new(secret: Vec<u8>) -> Self {
+        Self { secret: secret }
+    }
+
+    // Without FORM_SIGNING_SECRET, tokens issued before a restart become invalid.
+    pub fn from_env() -> Self {
+        match env::var("FORM_SIGNING_SECRET") {
+            Ok(secret) => Self::new(secret.into_bytes()),
+            Err(_) => Self::new(rand::thread_rng().gen::<[u8; 32]>().to_vec()),
+        }
+    }
+
+    // "<payload>.<signature>"
+    pub fn sign(&self, purpose: &str, payload: &str) -> String {
+        format!("{}.{}", payload, self.signature(purpose, payload))
+    }
+
+    // Returns the payload if the token is signed by this signer for the
+    // purpose.
+    pub fn verify(&self, purpose: &str, token: &str) -> Option<String> {
+        let mut parts = token.rsplitn(2, '.');
+        let signature = parts.next()?;
+        let payload = parts.next()?;
+        let expected = self.signature(purpose, payload);
+        if utils::constant_time_eq(expected.as_bytes(), signature.as_bytes()) {
+            Some(String::from(payload))
+        } else {
+            None
+        }
+    }
+
+    fn signature(&self, purpose: &str, payload: &str) -> String {
+        let mut mac = Hmac::<Sha256>::new_varkey(&self.secret).expect("invalid key length");
+        mac.input(format!("{}:{}", purpose, payload).as_bytes());
+        hex::encode(mac.result().code())
+    }
+}
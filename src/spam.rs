@@ -0,0 +1,105 @@
+use chrono::prelude::*;
+use signer::Signer;
+use std::env;
+
+const DEFAULT_MIN_SUBMIT_SECS: i64 = 3;
+// forms older than this must be loaded again, so a bot can not keep posting
+// with one rendered form
+const FORM_TTL_SECS: i64 = 60 * 60;
+const SIGNING_PURPOSE: &'static str = "rendered_at";
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SpamCheckError {
+    // the hidden field, which people cannot see, is filled
+    Honeypot,
+    // sent sooner than a person could write a question
+    TooFast,
+    // the form was not rendered by us, has expired, or was rendered before
+    // FORM_SIGNING_SECRET changed
+    InvalidForm,
+}
+
+impl SpamCheckError {
+    // Name of the counter for submissions silently stored as spam.
+    pub fn counter_name(&self) -> Option<&'static str> {
+        match self {
+            SpamCheckError::Honeypot => Some("honeypot"),
+            SpamCheckError::TooFast => Some("too_fast"),
+            SpamCheckError::InvalidForm => None,
+        }
+    }
+}
+
+// Catches bots which fill every field or post right after loading the form.
+pub struct SpamTrap {
+    signer: Signer,
+    min_submit_secs: i64,
+}
+
+#[test]
+fn check_at_test() {
+    let trap = SpamTrap::new(Signer::new(b"secret".to_vec()), 3);
+    let token = trap.rendered_at_token_at(1000);
+    assert_eq!(trap.check_at("", Some(&token), 1003), Ok(()));
+    assert_eq!(trap.check_at("", Some(&token), 1002), Err(SpamCheckError::TooFast));
+    assert_eq!(trap.check_at("http://example.com", Some(&token), 1003), Err(SpamCheckError::Honeypot));
+    assert_eq!(trap.check_at("", None, 1003), Err(SpamCheckError::InvalidForm));
+    assert_eq!(trap.check_at("", Some("900.forged"), 1003), Err(SpamCheckError::InvalidForm));
+    assert_eq!(trap.check_at("", Some(&token), 1000 + FORM_TTL_SECS), Ok(()));
+    assert_eq!(
+        trap.check_at("", Some(&token), 1000 + FORM_TTL_SECS + 1),
+        Err(SpamCheckError::InvalidForm)
+    );
+}
+
+impl SpamTrap {
+    pub fn new(signer: Signer, min_submit_secs: i64) -> Self {
+        Self {
+            signer: signer,
+            min_submit_secs: min_submit_secs,
+        }
+    }
+
+    pub fn from_env(signer: Signer) -> Self {
+        let min_submit_secs = env::var("QUESTION_MIN_SUBMIT_SECS")
+            .ok()
+            .and_then(|secs| secs.parse().ok())
+            .unwrap_or(DEFAULT_MIN_SUBMIT_SECS);
+        Self::new(signer, min_submit_secs)
+    }
+
+    // Embedded into the form as `rendered_at`.
+    pub fn rendered_at_token(&self) -> String {
+        self.rendered_at_token_at(Utc::now().timestamp())
+    }
+
+    fn rendered_at_token_at(&self, unixtime: i64) -> String {
+        self.signer.sign(SIGNING_PURPOSE, &unixtime.to_string())
+    }
+
+    pub fn check(&self, honeypot: &str, rendered_at_token: Option<&str>) -> Result<(), SpamCheckError> {
+        self.check_at(honeypot, rendered_at_token, Utc::now().timestamp())
+    }
+
+    fn check_at(
+        &self,
+        honeypot: &str,
+        rendered_at_token: Option<&str>,
+        unixtime: i64,
+    ) -> Result<(), SpamCheckError> {
+        if !honeypot.is_empty() {
+            return Err(SpamCheckError::Honeypot);
+        }
+        let rendered_at = rendered_at_token
+            .and_then(|token| self.signer.verify(SIGNING_PURPOSE, token))
+            .and_then(|payload| payload.parse::<i64>().ok())
+            .ok_or(SpamCheckError::InvalidForm)?;
+        if unixtime - rendered_at > FORM_TTL_SECS {
+            return Err(SpamCheckError::InvalidForm);
+        }
+        if unixtime - rendered_at < self.min_submit_secs {
+            return Err(SpamCheckError::TooFast);
+        }
+        Ok(())
+    }
+}
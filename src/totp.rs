@@ -0,0 +1,117 @@
+use base32;
+use hex;
+use hmac::{Hmac, Mac};
+use qrcode::render::svg;
+use qrcode::QrCode;
+use rand::Rng;
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+use url;
+use utils;
+
+// RFC 6238 defaults, which every authenticator app supports
+const STEP_SECS: i64 = 30;
+const DIGITS: u32 = 6;
+// accept codes from one step before and after to tolerate clock drift
+const ALLOWED_DRIFT_STEPS: i64 = 1;
+const RECOVERY_CODE_COUNT: usize = 10;
+
+#[test]
+fn code_at_test() {
+    // RFC 6238 Appendix B (SHA1), truncated to 6 digits
+    let secret = b"12345678901234567890";
+    assert_eq!(code_at(secret, 59), 287082);
+    assert_eq!(code_at(secret, 1111111109), 81804);
+    assert_eq!(code_at(secret, 1234567890), 5924);
+    assert_eq!(code_at(secret, 2000000000), 279037);
+}
+
+fn code_at(secret: &[u8], unixtime: i64) -> u32 {
+    let counter = (unixtime / STEP_SECS) as u64;
+    let mut mac = Hmac::<Sha1>::new_varkey(secret).expect("invalid key length");
+    mac.input(&counter.to_be_bytes());
+    let hash = mac.result().code();
+
+    // RFC 4226 dynamic truncation
+    let offset = (hash[hash.len() - 1] & 0x0f) as usize;
+    let binary = ((hash[offset] as u32 & 0x7f) << 24)
+        | ((hash[offset + 1] as u32) << 16)
+        | ((hash[offset + 2] as u32) << 8)
+        | (hash[offset + 3] as u32);
+    binary % 10u32.pow(DIGITS)
+}
+
+pub fn generate_secret() -> String {
+    let bytes = rand::thread_rng().gen::<[u8; 20]>();
+    base32::encode(base32::Alphabet::RFC4648 { padding: false }, &bytes)
+}
+
+#[test]
+fn verified_step_test() {
+    let secret = base32::encode(base32::Alphabet::RFC4648 { padding: false }, b"12345678901234567890");
+    assert_eq!(verified_step(&secret, "287082", 59), Some(1));
+    // from the previous step, within the allowed drift
+    assert_eq!(verified_step(&secret, "287082", 89), Some(1));
+    assert_eq!(verified_step(&secret, "287082", 150), None);
+    assert_eq!(verified_step(&secret, "000000", 59), None);
+}
+
+// The time step the code belongs to, so that callers can refuse to accept
+// the same step twice.
+pub fn verified_step(secret: &str, code: &str, unixtime: i64) -> Option<i64> {
+    let secret = base32::decode(base32::Alphabet::RFC4648 { padding: false }, secret)?;
+    let code = code.trim();
+    if code.len() != DIGITS as usize {
+        return None;
+    }
+
+    (-ALLOWED_DRIFT_STEPS..=ALLOWED_DRIFT_STEPS)
+        .map(|drift| unixtime / STEP_SECS + drift)
+        .fold(None, |verified, step| {
+            let expected = code_at(&secret, step * STEP_SECS);
+            if utils::constant_time_eq(format!("{:06}", expected).as_bytes(), code.as_bytes()) {
+                Some(step)
+            } else {
+                verified
+            }
+        })
+}
+
+pub fn provisioning_uri(secret: &str, account: &str, issuer: &str) -> String {
+    let label = format!("{}:{}", issuer, account);
+    let query = url::form_urlencoded::Serializer::new(String::new())
+        .append_pair("secret", secret)
+        .append_pair("issuer", issuer)
+        .finish();
+    format!(
+        "otpauth://totp/{}?{}",
+        url::form_urlencoded::byte_serialize(label.as_bytes()).collect::<String>(),
+        query
+    )
+}
+
+pub fn qr_code_svg(uri: &str) -> String {
+    QrCode::new(uri.as_bytes())
+        .expect("Failed to encode QR code.")
+        .render::<svg::Color>()
+        .min_dimensions(200, 200)
+        .build()
+}
+
+pub fn generate_recovery_codes() -> Vec<String> {
+    let mut rng = rand::thread_rng();
+    (0..RECOVERY_CODE_COUNT)
+        .map(|_| {
+            let bytes = rng.gen::<[u8; 5]>();
+            let code = hex::encode(&bytes);
+            format!("{}-{}", &code[..5], &code[5..])
+        })
+        .collect()
+}
+
+// Recovery codes are random, so a plain SHA-256 is enough to keep them
+// unusable if the database leaks.
+pub fn hash_recovery_code(code: &str) -> String {
+    let normalized = code.trim().to_lowercase().replace("-", "");
+    hex::encode(Sha256::digest(normalized.as_bytes()))
+}
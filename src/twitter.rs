@@ -0,0 +1,266 @@
+use base64;
+use chrono::prelude::*;
+use hmac::{Hmac, Mac};
+use rand::distributions::Alphanumeric;
+use rand::Rng;
+use reqwest;
+use sha1::Sha1;
+use std::env;
+use std::path::Path;
+use std::time::Duration;
+
+pub const SERVICE_NAME: &'static str = "twitter";
+
+const DEFAULT_API_BASE_URL: &'static str = "https://api.twitter.com";
+const DEFAULT_UPLOAD_BASE_URL: &'static str = "https://upload.twitter.com";
+
+#[derive(Deserialize, Debug)]
+struct Media {
+    pub media_id_string: String,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct User {
+    pub screen_name: String,
+    pub name: String,
+    pub profile_image_url_https: String,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Tweet {
+    pub id_str: String,
+    pub user: User,
+}
+
+impl Tweet {
+    pub fn permalink(&self) -> String {
+        format!(
+            "https://twitter.com/{}/status/{}",
+            self.user.screen_name, self.id_str
+        )
+    }
+}
+
+pub struct Credentials {
+    pub consumer_key: String,
+    pub consumer_secret: String,
+    pub access_token: String,
+    pub access_secret: String,
+}
+
+pub struct Client {
+    api_base_url: String,
+    upload_base_url: String,
+    credentials: Credentials,
+    http: reqwest::Client,
+}
+
+#[test]
+fn signature_test() {
+    // https://developer.twitter.com/en/docs/authentication/oauth-1-0a/creating-a-signature
+    let params = vec![
+        (String::from("status"), String::from("Hello Ladies + Gentlemen, a signed OAuth request!")),
+        (String::from("include_entities"), String::from("true")),
+        (String::from("oauth_consumer_key"), String::from("xvz1evFS4wEEPTGEFPHBog")),
+        (String::from("oauth_nonce"), String::from("kYjzVBB8Y0ZFabxSWbWovY3uYSQ2pTgmZeNu2VS4cg")),
+        (String::from("oauth_signature_method"), String::from("HMAC-SHA1")),
+        (String::from("oauth_timestamp"), String::from("1318622958")),
+        (String::from("oauth_token"), String::from("370773112-GmHxMAgYyLbNEtIKZeRNFsMKPR9EyMZeS9weJAEb")),
+        (String::from("oauth_version"), String::from("1.0")),
+    ];
+    assert_eq!(
+        signature(
+            "POST",
+            "https://api.twitter.com/1.1/statuses/update.json",
+            &params,
+            "kAcSOqF21Fu85e7zjz7ZN2U4ZRhfV3WpwPAoE3Z7kBw",
+            "LswwdoUaIvS8ltyTt5jkRh4J50vUPVVHtR2YPi5kE",
+        ),
+        "hCtSmYh+iHYCEqBWrE7C7hYmtUk="
+    );
+}
+
+#[test]
+fn post_tweet_test() {
+    let _media = mockito::mock("POST", "/1.1/media/upload.json")
+        .match_header("authorization", mockito::Matcher::Regex(String::from("^OAuth ")))
+        .with_body(r#"{"media_id_string":"10"}"#)
+        .create();
+    let _tweet = mockito::mock("POST", "/1.1/statuses/update.json")
+        .match_header("authorization", mockito::Matcher::Regex(String::from("oauth_signature=")))
+        .match_body(mockito::Matcher::UrlEncoded(
+            String::from("media_ids"),
+            String::from("10"),
+        ))
+        .with_body(r#"{"id_str":"20","user":{"screen_name":"reing","name":"Reing","profile_image_url_https":"https://example.com/reing.png"}}"#)
+        .create();
+
+    let credentials = Credentials {
+        consumer_key: String::from("consumer_key"),
+        consumer_secret: String::from("consumer_secret"),
+        access_token: String::from("access_token"),
+        access_secret: String::from("access_secret"),
+    };
+    let client = Client::new(mockito::server_url(), mockito::server_url(), credentials);
+    let tweet = client
+        .post_tweet("回答です", Some(Path::new("static/image/logo.png")))
+        .unwrap();
+    assert_eq!(tweet.id_str, "20");
+    assert_eq!(tweet.permalink(), "https://twitter.com/reing/status/20");
+}
+
+// RFC 3986 percent encoding as required by OAuth 1.0a
+fn percent_encode(s: &str) -> String {
+    s.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+fn signature(
+    method: &str,
+    url: &str,
+    params: &[(String, String)],
+    consumer_secret: &str,
+    token_secret: &str,
+) -> String {
+    let mut encoded_params = params
+        .iter()
+        .map(|(k, v)| (percent_encode(k), percent_encode(v)))
+        .collect::<Vec<_>>();
+    encoded_params.sort();
+    let param_string = encoded_params
+        .into_iter()
+        .map(|(k, v)| format!("{}={}", k, v))
+        .collect::<Vec<_>>()
+        .join("&");
+    let base_string = format!(
+        "{}&{}&{}",
+        method,
+        percent_encode(url),
+        percent_encode(&param_string)
+    );
+    let key = format!(
+        "{}&{}",
+        percent_encode(consumer_secret),
+        percent_encode(token_secret)
+    );
+
+    let mut mac = Hmac::<Sha1>::new_varkey(key.as_bytes()).expect("invalid key length");
+    mac.input(base_string.as_bytes());
+    base64::encode(&mac.result().code())
+}
+
+impl Client {
+    pub fn new(api_base_url: String, upload_base_url: String, credentials: Credentials) -> Self {
+        Self {
+            api_base_url: api_base_url.trim_end_matches('/').to_string(),
+            upload_base_url: upload_base_url.trim_end_matches('/').to_string(),
+            credentials: credentials,
+            http: reqwest::Client::builder()
+                .timeout(Duration::from_secs(30))
+                .build()
+                .expect("Failed to initialize http client."),
+        }
+    }
+
+    // Returns None unless all of the TWITTER_* credentials are set.
+    pub fn from_env() -> Option<Self> {
+        let credentials = Credentials {
+            consumer_key: env::var("TWITTER_CONSUMER_KEY").ok()?,
+            consumer_secret: env::var("TWITTER_CONSUMER_SECRET").ok()?,
+            access_token: env::var("TWITTER_ACCESS_TOKEN").ok()?,
+            access_secret: env::var("TWITTER_ACCESS_SECRET").ok()?,
+        };
+        Some(Self::new(
+            env::var("TWITTER_API_BASE_URL").unwrap_or(String::from(DEFAULT_API_BASE_URL)),
+            env::var("TWITTER_UPLOAD_BASE_URL").unwrap_or(String::from(DEFAULT_UPLOAD_BASE_URL)),
+            credentials,
+        ))
+    }
+
+    pub fn post_tweet(&self, text: &str, image: Option<&Path>) -> Result<Tweet, String> {
+        let mut params = vec![(String::from("status"), String::from(text))];
+        if let Some(path) = image {
+            let media = self.upload_media(path)?;
+            params.push((String::from("media_ids"), media.media_id_string));
+        }
+
+        let url = format!("{}/1.1/statuses/update.json", self.api_base_url);
+        self.http
+            .post(&url)
+            .header("Authorization", self.authorization("POST", &url, &params))
+            .form(&params)
+            .send()
+            .and_then(|response| response.error_for_status())
+            .and_then(|mut response| response.json::<Tweet>())
+            .map_err(|err| format!("{}", err))
+    }
+
+    pub fn show_user(&self, screen_name: &str) -> Result<User, String> {
+        let params = vec![(String::from("screen_name"), String::from(screen_name))];
+        let url = format!("{}/1.1/users/show.json", self.api_base_url);
+        self.http
+            .get(&url)
+            .header("Authorization", self.authorization("GET", &url, &params))
+            .query(&params)
+            .send()
+            .and_then(|response| response.error_for_status())
+            .and_then(|mut response| response.json::<User>())
+            .map_err(|err| format!("{}", err))
+    }
+
+    fn upload_media(&self, path: &Path) -> Result<Media, String> {
+        let form = reqwest::multipart::Form::new()
+            .file("media", path)
+            .map_err(|err| format!("{}", err))?;
+
+        // multipart parameters are not part of the signature base string
+        let url = format!("{}/1.1/media/upload.json", self.upload_base_url);
+        self.http
+            .post(&url)
+            .header("Authorization", self.authorization("POST", &url, &[]))
+            .multipart(form)
+            .send()
+            .and_then(|response| response.error_for_status())
+            .and_then(|mut response| response.json::<Media>())
+            .map_err(|err| format!("{}", err))
+    }
+
+    fn authorization(&self, method: &str, url: &str, request_params: &[(String, String)]) -> String {
+        let nonce = rand::thread_rng()
+            .sample_iter(&Alphanumeric)
+            .take(32)
+            .collect::<String>();
+        let mut oauth_params = vec![
+            (String::from("oauth_consumer_key"), self.credentials.consumer_key.clone()),
+            (String::from("oauth_nonce"), nonce),
+            (String::from("oauth_signature_method"), String::from("HMAC-SHA1")),
+            (String::from("oauth_timestamp"), Utc::now().timestamp().to_string()),
+            (String::from("oauth_token"), self.credentials.access_token.clone()),
+            (String::from("oauth_version"), String::from("1.0")),
+        ];
+
+        let mut signed_params = oauth_params.clone();
+        signed_params.extend_from_slice(request_params);
+        let signature = signature(
+            method,
+            url,
+            &signed_params,
+            &self.credentials.consumer_secret,
+            &self.credentials.access_secret,
+        );
+        oauth_params.push((String::from("oauth_signature"), signature));
+
+        let header_params = oauth_params
+            .into_iter()
+            .map(|(k, v)| format!("{}=\"{}\"", percent_encode(&k), percent_encode(&v)))
+            .collect::<Vec<_>>()
+            .join(", ");
+        format!("OAuth {}", header_params)
+    }
+}
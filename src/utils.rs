@@ -1,10 +1,123 @@
 use chrono::prelude::*;
 use chrono::Duration;
+use unicode_normalization::UnicodeNormalization;
+use url::Url;
 
 pub fn recognizable_datetime(datetime: DateTime<Local>) -> String {
     RecognizableDateTime::of(datetime, Local::now()).string()
 }
 
+pub fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+// For URLs from remote servers which end up in an href.
+pub fn is_http_url(s: &str) -> bool {
+    match Url::parse(s) {
+        Ok(url) => url.scheme() == "http" || url.scheme() == "https",
+        Err(_) => false,
+    }
+}
+
+// Parses "YYYY-MM-DD" as the beginning of the day in local time.
+pub fn parse_local_date(s: &str) -> Option<DateTime<Local>> {
+    let date = NaiveDate::parse_from_str(s.trim(), "%Y-%m-%d").ok()?;
+    Local.from_local_datetime(&date.and_hms(0, 0, 0)).earliest()
+}
+
+// Folds text for content matching: full-width/half-width forms (NFKC),
+// hiragana into katakana, and upper case into lower case.
+pub fn normalize_for_matching(text: &str) -> String {
+    fold_width_and_kana(text)
+        .chars()
+        .flat_map(|c| c.to_lowercase())
+        .collect()
+}
+
+// normalize_for_matching without the case folding, for regex patterns where
+// case is meaningful (`\D` is not `\d`).
+pub fn fold_width_and_kana(text: &str) -> String {
+    text.nfkc()
+        .map(|c| match c {
+            '\u{3041}'..='\u{3096}' | '\u{309D}'..='\u{309E}' => {
+                std::char::from_u32(c as u32 + 0x60).unwrap_or(c)
+            }
+            _ => c,
+        })
+        .collect()
+}
+
+// Dice coefficient of character bigrams, ignoring whitespace and
+// punctuation: 1.0 for the same text, 0.0 for nothing in common. Texts too
+// short to have bigrams (like "？" or "!!") are never similar.
+pub fn text_similarity(a: &str, b: &str) -> f64 {
+    fn bigrams(text: &str) -> Vec<(char, char)> {
+        let chars = normalize_for_matching(text)
+            .chars()
+            .filter(|c| c.is_alphanumeric())
+            .collect::<Vec<_>>();
+        let mut bigrams = chars.windows(2).map(|w| (w[0], w[1])).collect::<Vec<_>>();
+        bigrams.sort();
+        bigrams
+    }
+
+    let (a, b) = (bigrams(a), bigrams(b));
+    if a.is_empty() || b.is_empty() {
+        return 0.0;
+    }
+    // count common bigrams as a multiset intersection of the sorted lists
+    let (mut i, mut j, mut common) = (0, 0, 0);
+    while i < a.len() && j < b.len() {
+        if a[i] == b[j] {
+            common += 1;
+            i += 1;
+            j += 1;
+        } else if a[i] < b[j] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    2.0 * common as f64 / (a.len() + b.len()) as f64
+}
+
+#[test]
+fn test_text_similarity() {
+    assert_eq!(text_similarity("好きな食べ物は？", "好きな食べ物は？"), 1.0);
+    // 5 of 6 bigrams in common
+    assert!((text_similarity("好きな食べ物は？", "すきな食べ物は!!") - 10.0 / 12.0).abs() < 1e-9);
+    assert_eq!(text_similarity("Hello World", "hello, world"), 1.0);
+    assert_eq!(text_similarity("好きな食べ物は？", "最近読んだ本"), 0.0);
+    assert_eq!(text_similarity("？", "!!"), 0.0);
+    assert_eq!(text_similarity("a", "a"), 0.0);
+}
+
+#[test]
+fn test_normalize_for_matching() {
+    assert_eq!(normalize_for_matching("ＡｂＣ１２３"), "abc123");
+    assert_eq!(normalize_for_matching("ばか"), "バカ");
+    assert_eq!(normalize_for_matching("ﾊﾞｶ"), "バカ");
+    assert_eq!(normalize_for_matching("バカ"), "バカ");
+    assert_eq!(fold_width_and_kana("ばか\\D"), "バカ\\D");
+}
+
+#[test]
+fn test_is_http_url() {
+    assert!(is_http_url("https://example.com/@reing/1"));
+    assert!(is_http_url("http://example.com/"));
+    assert!(!is_http_url("javascript:alert(1)"));
+    assert!(!is_http_url("/relative"));
+}
+
+#[test]
+fn test_parse_local_date() {
+    let parsed = parse_local_date("2026-10-19").unwrap();
+    assert_eq!((parsed.year(), parsed.month(), parsed.day()), (2026, 10, 19));
+    assert_eq!((parsed.hour(), parsed.minute()), (0, 0));
+    assert!(parse_local_date("").is_none());
+    assert!(parse_local_date("2026-13-01").is_none());
+}
+
 #[test]
 fn test_recognizable_datetime() {
     let now = Local::now();
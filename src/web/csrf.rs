@@ -0,0 +1,172 @@
+use hex;
+use model::LengthLimits;
+use rand::Rng;
+use rocket::data::{self, Data, FromDataSimple};
+use rocket::fairing::{Fairing, Info, Kind};
+use rocket::http::{Cookie, Status};
+use rocket::request::{self, FormItems, FromForm, FromRequest};
+use rocket::{Outcome, Request, State};
+use std::io::{self, Read};
+use std::ops::Deref;
+use utils;
+
+const COOKIE_NAME: &'static str = "csrf_token";
+const FIELD_NAME: &'static str = "csrf_token";
+
+// The token of the current request, cached by CsrfFairing.
+struct CachedToken(Option<String>);
+
+/* Fairing CsrfFairing */
+// Issues a token per browser session and caches it for the guards below.
+// Guards cannot read the cookie themselves because handlers taking `Cookies`
+// would then receive an empty jar.
+
+pub struct CsrfFairing;
+
+impl Fairing for CsrfFairing {
+    fn info(&self) -> Info {
+        Info {
+            name: "CSRF token",
+            kind: Kind::Request,
+        }
+    }
+
+    fn on_request(&self, request: &mut Request, _: &Data) {
+        let existing = request
+            .cookies()
+            .get_private(COOKIE_NAME)
+            .map(|cookie| String::from(cookie.value()));
+        let token = existing.unwrap_or_else(|| {
+            let token = hex::encode(rand::thread_rng().gen::<[u8; 32]>());
+            request
+                .cookies()
+                .add_private(Cookie::new(COOKIE_NAME, token.clone()));
+            token
+        });
+        request.local_cache(|| CachedToken(Some(token)));
+    }
+}
+
+fn cached_token(request: &Request) -> Option<String> {
+    request.local_cache(|| CachedToken(None)).0.clone()
+}
+
+/* Guard CsrfToken */
+// The token to embed into forms as `csrf_token`.
+
+pub struct CsrfToken(pub String);
+
+impl<'a, 'r> FromRequest<'a, 'r> for CsrfToken {
+    type Error = ();
+
+    fn from_request(request: &'a Request<'r>) -> request::Outcome<Self, Self::Error> {
+        match cached_token(request) {
+            Some(token) => Outcome::Success(CsrfToken(token)),
+            None => Outcome::Failure((Status::InternalServerError, ())),
+        }
+    }
+}
+
+/* Guard CsrfForm */
+// Same as request::Form, but rejects the request with 403 unless the form
+// has a `csrf_token` field matching the token of the session.
+
+pub struct CsrfForm<T>(pub T);
+
+impl<T> Deref for CsrfForm<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+#[test]
+fn token_matches_test() {
+    let expected = Some(String::from("abc"));
+    assert!(token_matches(expected.clone(), "body=x&csrf_token=abc"));
+    // missing
+    assert!(!token_matches(expected.clone(), "body=x"));
+    // wrong
+    assert!(!token_matches(expected.clone(), "body=x&csrf_token=abd"));
+    assert!(!token_matches(expected.clone(), "csrf_token="));
+    // no session token
+    assert!(!token_matches(None, "csrf_token=abc"));
+}
+
+fn token_matches(expected: Option<String>, body: &str) -> bool {
+    let submitted = FormItems::from(body)
+        .find(|item| item.key.as_str() == FIELD_NAME)
+        .and_then(|item| item.value.url_decode().ok());
+    match (expected, submitted) {
+        (Some(expected), Some(submitted)) => {
+            utils::constant_time_eq(expected.as_bytes(), submitted.as_bytes())
+        }
+        _ => false,
+    }
+}
+
+#[test]
+fn read_body_test() {
+    let body = "a".repeat(100);
+    assert_eq!(read_body(body.as_bytes(), 100).unwrap(), Some(body.clone()));
+    let too_long = format!("{}a", body);
+    assert_eq!(read_body(too_long.as_bytes(), 100).unwrap(), None);
+}
+
+// None if the body is longer than the limit, so that a long form is
+// rejected rather than parsed cut off.
+fn read_body<R: Read>(data: R, limit: u64) -> io::Result<Option<String>> {
+    let mut body = String::new();
+    data.take(limit + 1).read_to_string(&mut body)?;
+    if body.len() as u64 > limit {
+        return Ok(None);
+    }
+    Ok(Some(body))
+}
+
+impl<T> FromDataSimple for CsrfForm<T>
+where
+    T: for<'f> FromForm<'f>,
+{
+    type Error = String;
+
+    fn from_data(request: &Request, data: Data) -> data::Outcome<Self, Self::Error> {
+        // bodies within the length limits always fit, so that they get the
+        // usual "too long" message instead of 413
+        let limit = match request.guard::<State<LengthLimits>>().succeeded() {
+            Some(limits) => limits.form_limit(),
+            None => LengthLimits::from_env().form_limit(),
+        };
+        let body = match read_body(data.open(), limit) {
+            Ok(Some(body)) => body,
+            Ok(None) => {
+                return Outcome::Failure((Status::PayloadTooLarge, String::from("form too large")))
+            }
+            Err(err) => return Outcome::Failure((Status::BadRequest, format!("{}", err))),
+        };
+
+        if !token_matches(cached_token(request), &body) {
+            return Outcome::Failure((Status::Forbidden, String::from("invalid csrf token")));
+        }
+
+        // not strict, so that `csrf_token` itself is ignored
+        match T::from_form(&mut FormItems::from(body.as_str()), false) {
+            Ok(form) => Outcome::Success(CsrfForm(form)),
+            Err(_) => Outcome::Failure((Status::UnprocessableEntity, String::from("invalid form"))),
+        }
+    }
+}
+
+/* NoFields */
+// For POST routes which have nothing but the token: CsrfForm<NoFields>
+
+pub struct NoFields;
+
+impl<'f> FromForm<'f> for NoFields {
+    type Error = ();
+
+    fn from_form(_items: &mut FormItems<'f>, _strict: bool) -> Result<Self, Self::Error> {
+        Ok(NoFields)
+    }
+}
@@ -1,18 +1,15 @@
-use base64;
-use diesel;
+use api_token;
+use chrono::prelude::*;
 use model;
-use r2d2;
-use r2d2_diesel;
-use rocket::http::Status;
+use rocket::http::{Cookie, Cookies, Status};
 use rocket::request::{self, FromRequest};
 use rocket::{Outcome, Request, State};
 use std::env;
+use std::net::IpAddr;
 use std::ops::Deref;
 
 /* Guard Repository */
 
-type DieselPool = r2d2::Pool<r2d2_diesel::ConnectionManager<diesel::PgConnection>>;
-
 pub struct Repository(pub model::Repository);
 
 impl Deref for Repository {
@@ -27,7 +24,7 @@ impl<'a, 'r> FromRequest<'a, 'r> for Repository {
     type Error = ();
 
     fn from_request(request: &'a Request<'r>) -> request::Outcome<Self, Self::Error> {
-        let pool = request.guard::<State<DieselPool>>()?;
+        let pool = request.guard::<State<model::DieselPool>>()?;
         match pool.get() {
             Ok(conn) => Outcome::Success(Repository(model::Repository::new(conn))),
             Err(_) => Outcome::Failure((Status::ServiceUnavailable, ())),
@@ -45,38 +42,257 @@ impl ClientIP {
     }
 }
 
+#[test]
+fn client_ip_test() {
+    let remote: Option<IpAddr> = "10.0.0.1".parse().ok();
+    // not behind a proxy: headers sent by the client are ignored
+    assert_eq!(client_ip(remote, &["203.0.113.9"], 0), "10.0.0.1");
+    // behind one proxy: only the hop appended by the proxy counts, so a
+    // spoofed X-Forwarded-For sent by the client makes no difference
+    assert_eq!(client_ip(remote, &["198.51.100.7"], 1), "198.51.100.7");
+    assert_eq!(client_ip(remote, &["203.0.113.9, 198.51.100.7"], 1), "198.51.100.7");
+    assert_eq!(client_ip(remote, &["203.0.113.9", "198.51.100.7"], 1), "198.51.100.7");
+    // missing or broken header
+    assert_eq!(client_ip(remote, &[], 1), "10.0.0.1");
+    assert_eq!(client_ip(remote, &["unknown"], 1), "10.0.0.1");
+    assert_eq!(client_ip(None, &[], 0), "");
+}
+
+// `trusted_hops` is the number of proxies in front of the server, each of
+// which appends the address it received the request from to
+// X-Forwarded-For. Anything before those entries is set by the client.
+fn client_ip(remote: Option<IpAddr>, forwarded_for: &[&str], trusted_hops: usize) -> String {
+    let hops = forwarded_for
+        .iter()
+        .flat_map(|header| header.split(','))
+        .map(|hop| hop.trim())
+        .collect::<Vec<_>>();
+    let forwarded = if trusted_hops > 0 && hops.len() >= trusted_hops {
+        hops[hops.len() - trusted_hops].parse::<IpAddr>().ok()
+    } else {
+        None
+    };
+    match forwarded.or(remote) {
+        Some(ip_address) => format!("{}", ip_address),
+        None => String::from(""),
+    }
+}
+
 impl<'a, 'r> FromRequest<'a, 'r> for ClientIP {
     type Error = ();
 
     fn from_request(request: &'a Request<'r>) -> request::Outcome<Self, Self::Error> {
-        let ip_address = match request.client_ip() {
-            Some(ip_address) => format!("{}", ip_address),
-            None => String::from(""),
+        // Request::client_ip would trust X-Real-IP from anyone
+        let trusted_hops = env::var("TRUSTED_PROXY_HOPS")
+            .ok()
+            .and_then(|hops| hops.parse().ok())
+            .unwrap_or(0);
+        let forwarded_for = request.headers().get("X-Forwarded-For").collect::<Vec<_>>();
+        let remote = request.remote().map(|address| address.ip());
+        Outcome::Success(ClientIP(client_ip(remote, &forwarded_for, trusted_hops)))
+    }
+}
+
+/* Guard AdminSession */
+
+const SESSION_COOKIE: &'static str = "admin_session";
+const DEFAULT_SESSION_TTL_SECS: i64 = 7 * 24 * 60 * 60;
+
+pub struct AdminSession {
+    pub username: String,
+}
+
+// Private cookies are encrypted and authenticated by Rocket's secret_key,
+// so their values can be trusted as long as they have not expired.
+fn add_expiring_cookie(cookies: &mut Cookies, name: &'static str, ttl: i64, username: &str) {
+    let expires_at = Utc::now().timestamp() + ttl;
+    cookies.add_private(Cookie::new(name, format!("{}:{}", expires_at, username)));
+}
+
+fn get_expiring_cookie(request: &Request, name: &str) -> Option<String> {
+    let cookie = request.cookies().get_private(name)?;
+    let mut parts = cookie.value().splitn(2, ':');
+    let expires_at = parts.next()?.parse::<i64>().ok()?;
+    let username = parts.next()?;
+    if expires_at < Utc::now().timestamp() {
+        return None;
+    }
+    Some(String::from(username))
+}
+
+impl AdminSession {
+    pub fn start(cookies: &mut Cookies, username: String) -> Self {
+        let ttl = env::var("ADMIN_SESSION_TTL_SECS")
+            .ok()
+            .and_then(|secs| secs.parse().ok())
+            .unwrap_or(DEFAULT_SESSION_TTL_SECS);
+        PendingLogin::end(cookies);
+        add_expiring_cookie(cookies, SESSION_COOKIE, ttl, &username);
+        AdminSession { username: username }
+    }
+
+    pub fn end(cookies: &mut Cookies) {
+        cookies.remove_private(Cookie::named(SESSION_COOKIE));
+    }
+}
+
+impl<'a, 'r> FromRequest<'a, 'r> for AdminSession {
+    type Error = ();
+
+    fn from_request(request: &'a Request<'r>) -> request::Outcome<Self, Self::Error> {
+        match get_expiring_cookie(request, SESSION_COOKIE) {
+            Some(username) => Outcome::Success(AdminSession { username: username }),
+            None => Outcome::Failure((Status::Unauthorized, ())),
+        }
+    }
+}
+
+/* Guard Admin */
+// A logged-in admin, loaded from the database so that a deleted admin or a
+// changed role takes effect without waiting for the session to expire.
+
+pub struct Admin(pub model::Admin);
+
+impl Deref for Admin {
+    type Target = model::Admin;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<'a, 'r> FromRequest<'a, 'r> for Admin {
+    type Error = ();
+
+    fn from_request(request: &'a Request<'r>) -> request::Outcome<Self, Self::Error> {
+        let session = request.guard::<AdminSession>()?;
+        let repo = request.guard::<Repository>()?;
+        match repo.find_admin(&session.username) {
+            Some(admin) => Outcome::Success(Admin(admin)),
+            None => Outcome::Failure((Status::Unauthorized, ())),
+        }
+    }
+}
+
+/* Guard Answerer */
+// An admin who is allowed to publish answers (owner or answerer).
+
+pub struct Answerer(pub Admin);
+
+impl<'a, 'r> FromRequest<'a, 'r> for Answerer {
+    type Error = ();
+
+    fn from_request(request: &'a Request<'r>) -> request::Outcome<Self, Self::Error> {
+        let admin = request.guard::<Admin>()?;
+        if admin.role.can_answer() {
+            Outcome::Success(Answerer(admin))
+        } else {
+            Outcome::Failure((Status::Forbidden, ()))
+        }
+    }
+}
+
+/* Guard Owner */
+
+pub struct Owner(pub Admin);
+
+impl<'a, 'r> FromRequest<'a, 'r> for Owner {
+    type Error = ();
+
+    fn from_request(request: &'a Request<'r>) -> request::Outcome<Self, Self::Error> {
+        let admin = request.guard::<Admin>()?;
+        if admin.role.can_manage() {
+            Outcome::Success(Owner(admin))
+        } else {
+            Outcome::Failure((Status::Forbidden, ()))
+        }
+    }
+}
+
+/* Guard ApiClient */
+// A script authenticated by `Authorization: Bearer <token>`, acting on behalf
+// of the admin who created the token. Any scope can read.
+
+pub struct ApiClient {
+    pub admin: model::Admin,
+    pub token: model::ApiToken,
+}
+
+impl ApiClient {
+    // for the audit log
+    pub fn actor(&self) -> String {
+        format!("{} (token: {})", self.admin.username, self.token.name)
+    }
+}
+
+impl<'a, 'r> FromRequest<'a, 'r> for ApiClient {
+    type Error = ();
+
+    fn from_request(request: &'a Request<'r>) -> request::Outcome<Self, Self::Error> {
+        let token = match request.headers().get_one("Authorization") {
+            Some(value) if value.starts_with("Bearer ") => &value["Bearer ".len()..],
+            _ => return Outcome::Failure((Status::Unauthorized, ())),
         };
-        Outcome::Success(ClientIP(ip_address))
+        let repo = request.guard::<Repository>()?;
+        let found = repo
+            .use_api_token(&api_token::hash(token))
+            .and_then(|token| repo.find_admin(&token.username).map(|admin| (admin, token)));
+        match found {
+            Some((admin, token)) => Outcome::Success(ApiClient {
+                admin: admin,
+                token: token,
+            }),
+            None => Outcome::Failure((Status::Unauthorized, ())),
+        }
     }
 }
 
-/* Guard BasicAuth */
+/* Guard ApiWriter */
 
-pub struct BasicAuth();
+pub struct ApiWriter(pub ApiClient);
 
-impl<'a, 'r> FromRequest<'a, 'r> for BasicAuth {
+impl<'a, 'r> FromRequest<'a, 'r> for ApiWriter {
     type Error = ();
 
     fn from_request(request: &'a Request<'r>) -> request::Outcome<Self, Self::Error> {
-        if let Some(sent_auth_code) = request.headers().get("Authorization").next() {
-            let username = env::var("ADMIN_USERNAME").unwrap();
-            let password = env::var("ADMIN_PASSWORD").unwrap();
-            let b64 = base64::encode(&format!("{}:{}", username, password));
-            let valid_auth_code = format!("Basic {}", b64);
-
-            if sent_auth_code == valid_auth_code {
-                return Outcome::Success(BasicAuth());
-            }
+        let client = request.guard::<ApiClient>()?;
+        if client.token.scope == model::TokenScope::Write {
+            Outcome::Success(ApiWriter(client))
+        } else {
+            Outcome::Failure((Status::Forbidden, ()))
         }
+    }
+}
+
+/* Guard PendingLogin */
+// An admin who has passed the password check but not the second factor yet.
+
+const PENDING_LOGIN_COOKIE: &'static str = "admin_pending_login";
+const PENDING_LOGIN_TTL_SECS: i64 = 5 * 60;
 
-        Outcome::Failure((Status::Unauthorized, ()))
+pub struct PendingLogin {
+    pub username: String,
+}
+
+impl PendingLogin {
+    pub fn start(cookies: &mut Cookies, username: String) -> Self {
+        add_expiring_cookie(cookies, PENDING_LOGIN_COOKIE, PENDING_LOGIN_TTL_SECS, &username);
+        PendingLogin { username: username }
+    }
+
+    pub fn end(cookies: &mut Cookies) {
+        cookies.remove_private(Cookie::named(PENDING_LOGIN_COOKIE));
+    }
+}
+
+impl<'a, 'r> FromRequest<'a, 'r> for PendingLogin {
+    type Error = ();
+
+    fn from_request(request: &'a Request<'r>) -> request::Outcome<Self, Self::Error> {
+        match get_expiring_cookie(request, PENDING_LOGIN_COOKIE) {
+            Some(username) => Outcome::Success(PendingLogin { username: username }),
+            None => Outcome::Failure((Status::Unauthorized, ())),
+        }
     }
 }
 
@@ -1,5 +1,6 @@
 use rocket::{response, request};
 
+pub mod csrf;
 pub mod guard;
 
 pub struct CachedFile(pub response::NamedFile);
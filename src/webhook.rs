@@ -0,0 +1,117 @@
+use hex;
+use hmac::{Hmac, Mac};
+use model;
+use reqwest;
+use serde::Serialize;
+use serde_json;
+use sha2::Sha256;
+use std::env;
+use std::thread;
+use std::time::Duration;
+
+#[derive(Clone, Copy, Debug)]
+pub enum Event {
+    AnswerCreated,
+    AnswerUpdated,
+    AnswerDeleted,
+}
+
+impl Event {
+    fn name(&self) -> &'static str {
+        match self {
+            Event::AnswerCreated => "answer.created",
+            Event::AnswerUpdated => "answer.updated",
+            Event::AnswerDeleted => "answer.deleted",
+        }
+    }
+}
+
+#[derive(Serialize, Debug)]
+struct Payload<'a, T: Serialize + 'a> {
+    pub event: &'static str,
+    pub answer: &'a T,
+}
+
+pub struct Webhooks {
+    endpoints: Vec<String>,
+    secret: String,
+}
+
+#[test]
+fn sign_test() {
+    // RFC 4231 test case 2
+    assert_eq!(
+        sign("Jefe", "what do ya want for nothing?"),
+        "sha256=5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+    );
+}
+
+fn sign(secret: &str, body: &str) -> String {
+    let mut mac = Hmac::<Sha256>::new_varkey(secret.as_bytes()).expect("invalid key length");
+    mac.input(body.as_bytes());
+    format!("sha256={}", hex::encode(mac.result().code()))
+}
+
+impl Webhooks {
+    pub fn from_env() -> Self {
+        let endpoints = env::var("WEBHOOK_URLS")
+            .map(|urls| {
+                urls.split(',')
+                    .map(|url| url.trim().to_string())
+                    .filter(|url| !url.is_empty())
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or(vec![]);
+        let secret = if endpoints.is_empty() {
+            String::new()
+        } else {
+            env::var("WEBHOOK_SECRET").expect("WEBHOOK_SECRET not specified")
+        };
+
+        Self {
+            endpoints: endpoints,
+            secret: secret,
+        }
+    }
+
+    pub fn deliver<T: Serialize>(&self, pool: &model::DieselPool, event: Event, answer: &T) {
+        if self.endpoints.is_empty() {
+            return;
+        }
+
+        let body = serde_json::to_string(&Payload {
+            event: event.name(),
+            answer: answer,
+        })
+        .expect("Failed to serialize webhook payload.");
+        let signature = sign(&self.secret, &body);
+        let endpoints = self.endpoints.clone();
+        let pool = pool.clone();
+
+        let builder = thread::Builder::new().name("deliver-webhook-thread".into());
+        builder.spawn(move || {
+            let client = reqwest::Client::builder()
+                .timeout(Duration::from_secs(10))
+                .build()
+                .expect("Failed to initialize http client.");
+            let repo = model::Repository::new(pool.get().expect("Failed to get connection."));
+            for url in endpoints {
+                let result = client
+                    .post(&url)
+                    .header("Content-Type", "application/json")
+                    .header("X-Reing-Event", event.name())
+                    .header("X-Reing-Signature", signature.as_str())
+                    .body(body.clone())
+                    .send()
+                    .map(|response| response.status().as_u16() as i32)
+                    .map_err(|err| format!("{}", err));
+                repo.store_webhook_delivery(
+                    String::from(event.name()),
+                    url,
+                    body.clone(),
+                    result,
+                );
+            }
+        }).unwrap();
+    }
+}
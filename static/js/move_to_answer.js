@@ -2,6 +2,7 @@ const app = new Vue({
     el: '#app',
     data: {
         answer: {
+            id: null,
             created_at_recognizable: '',
             body: '',
             question: {
@@ -0,0 +1,49 @@
+(function() {
+    const form = document.getElementById('question-form');
+    if (!form || !form.elements['challenge_token']) {
+        return;
+    }
+    const difficulty = parseInt(form.dataset.challengeDifficulty, 10);
+    const encoder = new TextEncoder();
+
+    function leadingZeroBits(bytes) {
+        let bits = 0;
+        for (const byte of bytes) {
+            if (byte === 0) {
+                bits += 8;
+                continue;
+            }
+            bits += Math.clz32(byte) - 24;
+            break;
+        }
+        return bits;
+    }
+
+    async function solve(token) {
+        for (let nonce = 0; ; nonce++) {
+            const digest = await crypto.subtle.digest('SHA-256', encoder.encode(token + ':' + nonce));
+            if (leadingZeroBits(new Uint8Array(digest)) >= difficulty) {
+                return String(nonce);
+            }
+        }
+    }
+
+    // start right away, so that it is usually done before the question is written
+    const solving = solve(form.elements['challenge_token'].value)
+        .then((nonce) => form.elements['challenge_nonce'].value = nonce);
+
+    let solved = false;
+    form.addEventListener('submit', (event) => {
+        if (solved) {
+            return;
+        }
+        event.preventDefault();
+        const button = form.querySelector('button[type=submit]');
+        button.disabled = true;
+        button.textContent = '送信の準備中…';
+        solving.then(() => {
+            solved = true;
+            form.submit();
+        });
+    });
+})()